@@ -1,4 +1,4 @@
-use coinbase_rs::{Public, MAIN_URL};
+use coinbase_rs::{ListOptions, PageStreamExt, Public, MAIN_URL};
 use futures::pin_mut;
 use futures::stream::StreamExt;
 
@@ -6,15 +6,14 @@ use futures::stream::StreamExt;
 async fn main() {
     let client = Public::new(MAIN_URL);
 
-    let currencies = client.currencies();
+    let currencies = client.currencies(&ListOptions::new()).items();
     pin_mut!(currencies);
 
-    while let Some(currencies_result) = currencies.next().await {
-        for currency in currencies_result.unwrap() {
-            println!(
-                "Currency {} mininum size = {}",
-                currency.name, currency.min_size
-            );
-        }
+    while let Some(currency) = currencies.next().await {
+        let currency = currency.unwrap();
+        println!(
+            "Currency {} mininum size = {}",
+            currency.name, currency.min_size
+        );
     }
 }
@@ -1,7 +1,6 @@
-use coinbase_rs::{Private, Uuid, MAIN_URL};
+use coinbase_rs::{ListOptions, Private, MAIN_URL};
 use futures::pin_mut;
 use futures::stream::StreamExt;
-use std::str::FromStr;
 
 pub const KEY: &str = "<put key here>";
 pub const SECRET: &str = "<put secret here>";
@@ -10,25 +9,16 @@ pub const SECRET: &str = "<put secret here>";
 async fn main() {
     let client = Private::new(MAIN_URL, KEY, SECRET);
 
-    let accounts = client.accounts();
-    pin_mut!(accounts);
+    let transactions = client.all_transactions(&ListOptions::new().limit(25), 4);
+    pin_mut!(transactions);
 
-    while let Some(account_result) = accounts.next().await {
-        for account in account_result.unwrap() {
-            println!("Account {}", account.currency.code);
-            if let Ok(id) = Uuid::from_str(&account.id) {
-                let transactions = client.transactions(&id);
-                pin_mut!(transactions);
-
-                while let Some(transactions_result) = transactions.next().await {
-                    for transaction in transactions_result.unwrap() {
-                        println!(
-                            "Transaction {} = {}",
-                            transaction.id, transaction.amount.amount
-                        );
-                    }
-                }
-            }
+    while let Some(result) = transactions.next().await {
+        match result {
+            Ok((account, transaction)) => println!(
+                "Account {}: transaction {} = {}",
+                account.currency.code, transaction.id, transaction.amount.amount
+            ),
+            Err(e) => eprintln!("Failed {}", e),
         }
     }
 }
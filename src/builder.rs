@@ -0,0 +1,391 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use http::header::{HeaderName, HeaderValue};
+use http::Uri;
+
+use crate::metrics::Metrics;
+use crate::proxy::Proxy;
+use crate::rate_limit::RateLimiter;
+use crate::request::{self, CB_VERSION, USER_AGENT};
+use crate::retry::RetryPolicy;
+use crate::transport::{self, HyperTransport, Transport};
+use crate::{CBError, Private, Public, Result, MAIN_URL};
+
+// Headers managed by the request builder itself, which can't be overridden through defaults.
+const RESERVED_HEADERS: &[&str] = &[
+    "user-agent",
+    "cb-version",
+    "cb-access-key",
+    "cb-access-sign",
+    "cb-access-timestamp",
+    "cb-2fa-token",
+    "content-type",
+];
+
+///
+/// Configures and validates a `Public` or `Private` client.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use coinbase_rs::ClientBuilder;
+///
+/// let client = ClientBuilder::new()
+///     .request_timeout(Duration::from_secs(10))
+///     .cb_version("2021-01-01")
+///     .user_agent_suffix("my-app/1.0")
+///     .build_public()
+///     .unwrap();
+/// ```
+///
+/// Connection settings (connect timeout, pool and proxy) only apply to the default transport, they are
+/// ignored when a custom `Transport` is supplied.
+///
+#[derive(Clone)]
+pub struct ClientBuilder {
+    base_url: String,
+    request_timeout: Option<Duration>,
+    options: transport::Options,
+    cb_version: String,
+    user_agent_suffix: Option<String>,
+    default_headers: Vec<(String, String)>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry_policy: RetryPolicy,
+    transport: Option<Arc<dyn Transport>>,
+    proxy_from_env: bool,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self {
+            base_url: MAIN_URL.to_string(),
+            request_timeout: None,
+            options: transport::Options::default(),
+            cb_version: CB_VERSION.to_string(),
+            user_agent_suffix: None,
+            default_headers: Vec::new(),
+            rate_limiter: None,
+            retry_policy: RetryPolicy::default(),
+            transport: None,
+            proxy_from_env: false,
+            metrics: None,
+        }
+    }
+
+    /// The API root, defaults to `MAIN_URL`.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// Maximum time for a single attempt, from sending the request to receiving the headers.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.options.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.options.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.options.pool_max_idle_per_host = max;
+        self
+    }
+
+    /// The API version date sent as `CB-VERSION`, in `YYYY-MM-DD` format.
+    pub fn cb_version(mut self, cb_version: &str) -> Self {
+        self.cb_version = cb_version.to_string();
+        self
+    }
+
+    /// Appended to the crate's own `User-Agent`, e.g. `my-app/1.0`.
+    pub fn user_agent_suffix(mut self, suffix: &str) -> Self {
+        self.user_agent_suffix = Some(suffix.to_string());
+        self
+    }
+
+    /// A header sent with every request. Can't be used for the signing, version, content type
+    /// or two factor headers.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.default_headers
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Send every connection, REST or streaming, through `proxy`.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.options.proxy = Some(proxy);
+        self
+    }
+
+    ///
+    /// Use the proxy configured through `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`, if any. An
+    /// explicit `proxy` takes precedence.
+    ///
+    pub fn proxy_from_env(mut self) -> Self {
+        self.proxy_from_env = true;
+        self
+    }
+
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Report the outcome of every call to `metrics`.
+    pub fn metrics<M>(mut self, metrics: M) -> Self
+    where
+        M: Metrics + 'static,
+    {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    pub fn transport<T>(mut self, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    pub fn build_public(mut self) -> Result<Public> {
+        let defaults = self.validate()?;
+        if self.proxy_from_env && self.options.proxy.is_none() {
+            self.options.proxy = Proxy::from_env()?;
+        }
+        let base_url = self.base_url.trim_end_matches('/').to_string();
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => Arc::new(HyperTransport::with_options(&self.options)?),
+        };
+        let rate_limiter = self.rate_limiter.unwrap_or_default();
+
+        Ok(Public::from_parts(
+            base_url,
+            transport,
+            rate_limiter,
+            self.retry_policy,
+            defaults,
+            self.metrics,
+        ))
+    }
+
+    pub fn build_private(self, key: &str, secret: &str) -> Result<Private> {
+        if key.is_empty() || secret.is_empty() {
+            return Err(config_error("API key and secret must not be empty"));
+        }
+        Ok(Private::from_parts(self.build_public()?, key, secret))
+    }
+
+    fn validate(&self) -> Result<Defaults> {
+        let uri: Uri = self
+            .base_url
+            .parse()
+            .map_err(|e| config_error(format!("invalid base URL {}: {}", self.base_url, e)))?;
+        match uri.scheme_str() {
+            Some("http") | Some("https") => {}
+            _ => {
+                return Err(config_error(format!(
+                    "base URL {} must be http or https",
+                    self.base_url
+                )))
+            }
+        }
+        if uri.host().is_none() || uri.query().is_some() {
+            return Err(config_error(format!(
+                "base URL {} must have a host and no query",
+                self.base_url
+            )));
+        }
+
+        let timeouts = [
+            self.request_timeout,
+            self.options.connect_timeout,
+            self.options.pool_idle_timeout,
+        ];
+        if timeouts.iter().flatten().any(Duration::is_zero) {
+            return Err(config_error("timeouts must be greater than zero"));
+        }
+
+        NaiveDate::parse_from_str(&self.cb_version, "%Y-%m-%d").map_err(|_| {
+            config_error(format!(
+                "CB-VERSION {} must be a date in YYYY-MM-DD format",
+                self.cb_version
+            ))
+        })?;
+
+        let user_agent = match self.user_agent_suffix {
+            Some(ref suffix) => format!("{} {}", USER_AGENT, suffix),
+            None => USER_AGENT.to_string(),
+        };
+        HeaderValue::from_str(&user_agent)
+            .map_err(|_| config_error(format!("invalid User-Agent {}", user_agent)))?;
+
+        for (name, value) in &self.default_headers {
+            let header = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| config_error(format!("invalid header name {}", name)))?;
+            if RESERVED_HEADERS.contains(&header.as_str()) {
+                return Err(config_error(format!(
+                    "header {} is set by the client and can't be overridden",
+                    name
+                )));
+            }
+            HeaderValue::from_str(value)
+                .map_err(|_| config_error(format!("invalid value for header {}", name)))?;
+        }
+
+        Ok(Defaults {
+            cb_version: self.cb_version.clone(),
+            user_agent,
+            headers: self.default_headers.clone(),
+            request_timeout: self.request_timeout,
+        })
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Settings applied to every request a client sends.
+#[derive(Clone, Debug)]
+pub(crate) struct Defaults {
+    pub(crate) cb_version: String,
+    pub(crate) user_agent: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) request_timeout: Option<Duration>,
+}
+
+impl Defaults {
+    pub(crate) fn apply(&self, request: request::Builder) -> request::Builder {
+        let mut request = request
+            .cb_version(&self.cb_version)
+            .user_agent(&self.user_agent);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request
+    }
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Self {
+            cb_version: CB_VERSION.to_string(),
+            user_agent: USER_AGENT.to_string(),
+            headers: Vec::new(),
+            request_timeout: None,
+        }
+    }
+}
+
+fn config_error<S: Into<String>>(message: S) -> CBError {
+    CBError::Config(message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use http::StatusCode;
+
+    use super::*;
+    use crate::transport::mock::MockTransport;
+
+    #[test]
+    fn test_validate_base_url() {
+        assert!(ClientBuilder::new().build_public().is_ok());
+        assert!(ClientBuilder::new()
+            .base_url("https://api.coinbase.com/")
+            .build_public()
+            .is_ok());
+        assert!(matches!(
+            ClientBuilder::new()
+                .base_url("api.coinbase.com")
+                .build_public(),
+            Err(CBError::Config(_))
+        ));
+        assert!(matches!(
+            ClientBuilder::new()
+                .base_url("ftp://api.coinbase.com")
+                .build_public(),
+            Err(CBError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        assert!(ClientBuilder::new()
+            .cb_version("2021-13-01")
+            .build_public()
+            .is_err());
+        assert!(ClientBuilder::new()
+            .request_timeout(Duration::from_secs(0))
+            .build_public()
+            .is_err());
+        assert!(ClientBuilder::new()
+            .user_agent_suffix("bad\nagent")
+            .build_public()
+            .is_err());
+        assert!(ClientBuilder::new()
+            .default_header("CB-ACCESS-KEY", "abc")
+            .build_public()
+            .is_err());
+        assert!(ClientBuilder::new()
+            .default_header("content-type", "text/plain")
+            .build_private("key", "secret")
+            .is_err());
+        assert!(ClientBuilder::new()
+            .default_header("CB-2FA-TOKEN", "1234567")
+            .build_private("key", "secret")
+            .is_err());
+        assert!(ClientBuilder::new()
+            .default_header("bad header", "abc")
+            .build_public()
+            .is_err());
+        assert!(ClientBuilder::new().build_private("", "secret").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_defaults_are_sent() {
+        let transport = Arc::new(MockTransport::default().respond(
+            StatusCode::OK,
+            r#"{"data": {"iso": "2015-06-23T18:02:51Z", "epoch": 1435082571}}"#,
+        ));
+        let client = ClientBuilder::new()
+            .base_url("https://sandbox.example.com/")
+            .cb_version("2019-04-03")
+            .user_agent_suffix("my-app/1.0")
+            .default_header("X-Tenant", "acme")
+            .transport(transport.clone())
+            .build_public()
+            .unwrap();
+
+        client.current_time().await.unwrap();
+
+        let requests = transport.requests.lock().unwrap();
+        let request = &requests[0];
+        assert_eq!(request.uri(), "https://sandbox.example.com/v2/time");
+        assert_eq!(request.headers()["CB-VERSION"], "2019-04-03");
+        assert_eq!(
+            request.headers()["User-Agent"],
+            format!("{} my-app/1.0", USER_AGENT).as_str()
+        );
+        assert_eq!(request.headers()["X-Tenant"], "acme");
+    }
+}
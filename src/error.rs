@@ -1,15 +1,197 @@
 use std::fmt;
 
+use bigdecimal::BigDecimal;
+use http::{HeaderMap, StatusCode};
 use thiserror::Error;
 
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// Error codes returned in the `errors[].id` field of a failed response.
+///
+/// https://docs.cloud.coinbase.com/sign-in-with-coinbase/docs/status-codes
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum ErrorCode {
+    TwoFactorRequired,
+    ParamRequired,
+    ValidationError,
+    InvalidRequest,
+    PersonalDetailsRequired,
+    IdentityVerificationRequired,
+    JumioVerificationRequired,
+    JumioFaceMatchVerificationRequired,
+    UnverifiedEmail,
+    AuthenticationError,
+    InvalidToken,
+    RevokedToken,
+    ExpiredToken,
+    InvalidScope,
+    NotFound,
+    RateLimitExceeded,
+    InternalServerError,
+    Other(String),
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ErrorCode::TwoFactorRequired => "two_factor_required",
+            ErrorCode::ParamRequired => "param_required",
+            ErrorCode::ValidationError => "validation_error",
+            ErrorCode::InvalidRequest => "invalid_request",
+            ErrorCode::PersonalDetailsRequired => "personal_details_required",
+            ErrorCode::IdentityVerificationRequired => "identity_verification_required",
+            ErrorCode::JumioVerificationRequired => "jumio_verification_required",
+            ErrorCode::JumioFaceMatchVerificationRequired => {
+                "jumio_face_match_verification_required"
+            }
+            ErrorCode::UnverifiedEmail => "unverified_email",
+            ErrorCode::AuthenticationError => "authentication_error",
+            ErrorCode::InvalidToken => "invalid_token",
+            ErrorCode::RevokedToken => "revoked_token",
+            ErrorCode::ExpiredToken => "expired_token",
+            ErrorCode::InvalidScope => "invalid_scope",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::RateLimitExceeded => "rate_limit_exceeded",
+            ErrorCode::InternalServerError => "internal_server_error",
+            ErrorCode::Other(code) => code,
+        }
+    }
+}
+
+impl From<String> for ErrorCode {
+    fn from(code: String) -> Self {
+        match code.as_str() {
+            "two_factor_required" => ErrorCode::TwoFactorRequired,
+            "param_required" => ErrorCode::ParamRequired,
+            "validation_error" => ErrorCode::ValidationError,
+            "invalid_request" => ErrorCode::InvalidRequest,
+            "personal_details_required" => ErrorCode::PersonalDetailsRequired,
+            "identity_verification_required" => ErrorCode::IdentityVerificationRequired,
+            "jumio_verification_required" => ErrorCode::JumioVerificationRequired,
+            "jumio_face_match_verification_required" => {
+                ErrorCode::JumioFaceMatchVerificationRequired
+            }
+            "unverified_email" => ErrorCode::UnverifiedEmail,
+            "authentication_error" => ErrorCode::AuthenticationError,
+            "invalid_token" => ErrorCode::InvalidToken,
+            "revoked_token" => ErrorCode::RevokedToken,
+            "expired_token" => ErrorCode::ExpiredToken,
+            "invalid_scope" => ErrorCode::InvalidScope,
+            "not_found" => ErrorCode::NotFound,
+            "rate_limit_exceeded" => ErrorCode::RateLimitExceeded,
+            "internal_server_error" => ErrorCode::InternalServerError,
+            _ => ErrorCode::Other(code),
+        }
+    }
+}
+
+impl From<ErrorCode> for String {
+    fn from(code: ErrorCode) -> Self {
+        code.as_str().to_string()
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ErrorDetail {
+    pub id: ErrorCode,
+    pub message: String,
+    pub url: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Warning {
+    pub id: String,
+    pub message: String,
+    pub url: Option<String>,
+}
+
+///
+/// A non-successful response from the API.
+///
+/// `errors` and `warnings` are parsed from the response envelope when it is JSON, `body` always
+/// holds the raw response, e.g. the HTML page of a 502 from a load balancer.
+///
+#[derive(Clone, Debug)]
 pub struct Error {
-    message: String,
+    pub status: StatusCode,
+    pub errors: Vec<ErrorDetail>,
+    pub warnings: Vec<Warning>,
+    pub request_id: Option<String>,
+    pub body: String,
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    errors: Vec<ErrorDetail>,
+    #[serde(default)]
+    warnings: Vec<Warning>,
+    // OAuth2 endpoints report errors in the format of RFC 6749
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+impl Error {
+    pub(crate) fn new(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> Self {
+        let request_id = ["cb-request-id", "x-request-id"]
+            .iter()
+            .find_map(|name| headers.get(*name))
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let (errors, warnings) = match serde_json::from_slice::<Envelope>(body) {
+            Ok(mut envelope) => {
+                if let Some(error) = envelope.error {
+                    envelope.errors.push(ErrorDetail {
+                        id: error.into(),
+                        message: envelope.error_description.unwrap_or_default(),
+                        url: None,
+                    });
+                }
+                (envelope.errors, envelope.warnings)
+            }
+            Err(_) => (Vec::new(), Vec::new()),
+        };
+
+        Self {
+            status,
+            errors,
+            warnings,
+            request_id,
+            body: String::from_utf8_lossy(body).into_owned(),
+        }
+    }
+
+    /// The code of the first error reported, if any.
+    pub fn code(&self) -> Option<&ErrorCode> {
+        self.errors.first().map(|error| &error.id)
+    }
+
+    pub fn has_code(&self, code: &ErrorCode) -> bool {
+        self.errors.iter().any(|error| &error.id == code)
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
+        write!(f, "{}", self.status)?;
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+
+        let messages: Vec<_> = self
+            .errors
+            .iter()
+            .map(|error| format!("{} ({})", error.message, error.id))
+            .collect();
+        write!(f, ": {}", messages.join(", "))
     }
 }
 
@@ -21,4 +203,136 @@ pub enum CBError {
     Serde(#[from] super::serde_json::Error),
     #[error("coinbase: {0}")]
     Coinbase(Error),
+    #[error("transport error {0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    #[error("request timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("invalid client configuration: {0}")]
+    Config(String),
+    #[error("invalid request: {0}")]
+    InvalidRequest(#[from] super::request::Error),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+}
+
+///
+/// A request rejected by the client before it was sent, as the API would refuse it.
+///
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("account {account} holds {account_currency}, not {currency}")]
+    CurrencyMismatch {
+        account: String,
+        account_currency: String,
+        currency: String,
+    },
+    #[error("can't transfer from account {account} to itself")]
+    SameAccount { account: String },
+    #[error("insufficient funds: {available} {currency} available, {requested} requested")]
+    InsufficientFunds {
+        available: BigDecimal,
+        requested: BigDecimal,
+        currency: String,
+    },
+}
+
+impl CBError {
+    /// The HTTP status of the response, if the request got that far.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            CBError::Coinbase(error) => Some(error.status),
+            _ => None,
+        }
+    }
+
+    /// The code of the first error reported by the API, if any.
+    pub fn code(&self) -> Option<&ErrorCode> {
+        match self {
+            CBError::Coinbase(error) => error.code(),
+            _ => None,
+        }
+    }
+
+    /// Whether the call must be repeated with a `CB-2FA-TOKEN`.
+    pub fn is_two_factor_required(&self) -> bool {
+        match self {
+            CBError::Coinbase(error) => error.has_code(&ErrorCode::TwoFactorRequired),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_envelope() {
+        let input = r#"
+{
+  "errors": [
+    {
+      "id": "not_found",
+      "message": "Not found",
+      "url": "https://developers.coinbase.com/api/v2#errors"
+    }
+  ],
+  "warnings": [
+    {
+      "id": "missing_version",
+      "message": "Please supply API version (YYYY-MM-DD) as CB-VERSION header",
+      "url": "https://developers.coinbase.com/api#versioning"
+    }
+  ]
+}"#;
+        let mut headers = HeaderMap::new();
+        headers.insert("cb-request-id", "6d5d1ca0".parse().unwrap());
+        let error = Error::new(StatusCode::NOT_FOUND, &headers, input.as_bytes());
+
+        assert_eq!(error.status, StatusCode::NOT_FOUND);
+        assert_eq!(error.code(), Some(&ErrorCode::NotFound));
+        assert_eq!(
+            error.errors[0].url.as_deref(),
+            Some("https://developers.coinbase.com/api/v2#errors")
+        );
+        assert_eq!(error.warnings[0].id, "missing_version");
+        assert_eq!(error.request_id.as_deref(), Some("6d5d1ca0"));
+        assert_eq!(error.to_string(), "404 Not Found: Not found (not_found)");
+    }
+
+    #[test]
+    fn test_error_unknown_code() {
+        let input = r#"{"errors": [{"id": "something_new", "message": "Hmm"}]}"#;
+        let error = Error::new(StatusCode::BAD_REQUEST, &HeaderMap::new(), input.as_bytes());
+
+        assert_eq!(
+            error.code(),
+            Some(&ErrorCode::Other("something_new".to_string()))
+        );
+        assert!(!error.has_code(&ErrorCode::NotFound));
+    }
+
+    #[test]
+    fn test_error_oauth() {
+        let input =
+            r#"{"error": "invalid_token", "error_description": "The access token is invalid"}"#;
+        let error = Error::new(
+            StatusCode::UNAUTHORIZED,
+            &HeaderMap::new(),
+            input.as_bytes(),
+        );
+
+        assert!(error.has_code(&ErrorCode::InvalidToken));
+        assert_eq!(error.errors[0].message, "The access token is invalid");
+    }
+
+    #[test]
+    fn test_error_html_body() {
+        let input = "<html><body><h1>502 Bad Gateway</h1></body></html>";
+        let error = Error::new(StatusCode::BAD_GATEWAY, &HeaderMap::new(), input.as_bytes());
+
+        assert!(error.errors.is_empty());
+        assert_eq!(error.body, input);
+        assert_eq!(error.to_string(), "502 Bad Gateway");
+    }
 }
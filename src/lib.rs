@@ -3,24 +3,45 @@ extern crate futures;
 extern crate hmac;
 extern crate http;
 extern crate hyper;
-extern crate hyper_tls;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
 extern crate sha2;
 extern crate tokio;
+extern crate tokio_socks;
 extern crate tokio_stream;
 extern crate uritemplate;
 
+#[cfg(not(any(
+    feature = "native-tls",
+    feature = "rustls-webpki-roots",
+    feature = "rustls-native-roots"
+)))]
+compile_error!("one of the native-tls, rustls or rustls-native-roots features must be enabled");
+
+pub mod builder;
 pub mod error;
+pub mod metrics;
+pub mod pagination;
 pub mod private;
+pub mod proxy;
 pub mod public;
+pub mod rate_limit;
 pub mod request;
+pub mod retry;
+mod trace;
+pub mod transport;
 
-pub use error::CBError;
-pub use private::Private;
+pub use builder::ClientBuilder;
+pub use error::{CBError, ErrorCode, ValidationError};
+pub use metrics::Metrics;
+pub use pagination::{Direction, ListOptions, Page, PageStreamExt};
+pub use private::{AccountError, AccountId, Private};
 pub use public::Public;
+pub use rate_limit::{Limit, RateLimiter};
+pub use retry::RetryPolicy;
+pub use transport::{HyperTransport, Transport};
 
 pub const MAIN_URL: &str = "https://api.coinbase.com";
 
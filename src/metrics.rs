@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{HeaderMap, Method, StatusCode};
+
+///
+/// The outcome of one API call, across all of its attempts.
+///
+#[derive(Clone, Debug)]
+pub struct RequestMetrics<'a> {
+    /// The endpoint template, e.g. `/v2/accounts/{account}/transactions`, so ids don't end up in
+    /// metric labels.
+    pub endpoint: &'a str,
+    pub method: &'a Method,
+    /// `None` when no response was received, e.g. on a connection error or timeout.
+    pub status: Option<StatusCode>,
+    pub latency: Duration,
+    /// Size of the response body.
+    pub bytes: usize,
+    pub attempts: u32,
+    pub rate_limit: RateLimitStatus,
+}
+
+///
+/// Rate limit headroom, as reported through the `X-RateLimit-*` (or `RateLimit-*`) response
+/// headers. Fields are `None` when the API didn't send the corresponding header.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// Seconds until the budget resets, or the unix timestamp of the reset, as sent by the API.
+    pub reset: Option<u64>,
+}
+
+impl RateLimitStatus {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name: &str| {
+            [
+                format!("x-ratelimit-{}", name),
+                format!("ratelimit-{}", name),
+            ]
+            .iter()
+            .find_map(|name| headers.get(name.as_str()))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+        };
+
+        Self {
+            limit: header("limit"),
+            remaining: header("remaining"),
+            reset: header("reset"),
+        }
+    }
+}
+
+///
+/// Receives the outcome of every API call a client makes, e.g. to export them to Prometheus.
+///
+pub trait Metrics: Send + Sync {
+    fn record(&self, metrics: &RequestMetrics<'_>);
+}
+
+impl<T> Metrics for Arc<T>
+where
+    T: Metrics + ?Sized,
+{
+    fn record(&self, metrics: &RequestMetrics<'_>) {
+        (**self).record(metrics)
+    }
+}
+
+///
+/// Reports to the `metrics` crate facade, enabled through the `metrics` feature.
+///
+/// Records `coinbase_requests_total`, `coinbase_request_duration_seconds`,
+/// `coinbase_response_bytes` and `coinbase_request_attempts` labelled by endpoint, method and
+/// status, along with the `coinbase_rate_limit_limit`, `coinbase_rate_limit_remaining` and
+/// `coinbase_rate_limit_reset_seconds` gauges when the API reports them.
+///
+#[cfg(feature = "metrics")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MetricsRecorder;
+
+#[cfg(feature = "metrics")]
+impl Metrics for MetricsRecorder {
+    fn record(&self, metrics: &RequestMetrics<'_>) {
+        let labels = [
+            ("endpoint", metrics.endpoint.to_string()),
+            ("method", metrics.method.to_string()),
+            (
+                "status",
+                metrics
+                    .status
+                    .map(|status| status.as_u16().to_string())
+                    .unwrap_or_else(|| "error".to_string()),
+            ),
+        ];
+
+        ::metrics::counter!("coinbase_requests_total", &labels).increment(1);
+        ::metrics::histogram!("coinbase_request_duration_seconds", &labels)
+            .record(metrics.latency.as_secs_f64());
+        ::metrics::histogram!("coinbase_response_bytes", &labels).record(metrics.bytes as f64);
+        ::metrics::histogram!("coinbase_request_attempts", &labels)
+            .record(f64::from(metrics.attempts));
+        let gauges = [
+            ("coinbase_rate_limit_limit", metrics.rate_limit.limit),
+            (
+                "coinbase_rate_limit_remaining",
+                metrics.rate_limit.remaining,
+            ),
+            (
+                "coinbase_rate_limit_reset_seconds",
+                metrics.rate_limit.reset,
+            ),
+        ];
+        for (name, value) in gauges {
+            if let Some(value) = value {
+                ::metrics::gauge!(name).set(value as f64);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_status_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            RateLimitStatus::from_headers(&headers),
+            RateLimitStatus::default()
+        );
+
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("10000"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("9998"));
+        headers.insert("ratelimit-reset", HeaderValue::from_static("3599"));
+        assert_eq!(
+            RateLimitStatus::from_headers(&headers),
+            RateLimitStatus {
+                limit: Some(10000),
+                remaining: Some(9998),
+                reset: Some(3599),
+            }
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+
+use crate::Result;
+
+/// Page size used when `ListOptions::limit` isn't set.
+pub const DEFAULT_LIMIT: usize = 100;
+/// The largest page size the API accepts.
+pub const MAX_LIMIT: usize = 100;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Order {
+    #[serde(rename = "asc")]
+    Ascending,
+    #[serde(rename = "desc")]
+    Descending,
+}
+
+impl Order {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Order::Ascending => "asc",
+            Order::Descending => "desc",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Pagination {
+    pub ending_before: Option<String>,
+    pub starting_after: Option<String>,
+    pub previous_ending_before: Option<String>,
+    pub next_starting_after: Option<String>,
+    pub limit: usize,
+    pub order: Order,
+    pub previous_uri: Option<String>,
+    pub next_uri: Option<String>,
+}
+
+impl Pagination {
+    /// The page to fetch after this one when walking in `direction`.
+    pub(crate) fn uri(&self, direction: Direction) -> Option<&str> {
+        match direction {
+            Direction::Forward => self.next_uri.as_deref(),
+            Direction::Backward => self.previous_uri.as_deref(),
+        }
+    }
+}
+
+///
+/// Which way a paginated stream walks from its first page.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Direction {
+    /// Follow `next_uri`, i.e. older records in the default descending order.
+    #[default]
+    Forward,
+    /// Follow `previous_uri`, i.e. newer records in the default descending order.
+    Backward,
+}
+
+///
+/// One page of a list endpoint, with the cursors needed to resume after it.
+///
+/// Iterating over a page yields its items, so `PageStreamExt::items` works on any paginated
+/// stream.
+///
+#[derive(Clone, Debug)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub pagination: Option<Pagination>,
+}
+
+impl<T> Page<T> {
+    ///
+    /// The cursor to pass to `ListOptions::starting_after` to continue after this page, or `None`
+    /// on the last page.
+    ///
+    /// Save it once the page has been processed, so an interrupted sync can resume where it
+    /// stopped.
+    ///
+    pub fn next_cursor(&self) -> Option<&str> {
+        self.pagination
+            .as_ref()
+            .and_then(|pagination| pagination.next_starting_after.as_deref())
+    }
+
+    ///
+    /// The cursor to pass to `ListOptions::ending_before` to continue before this page, or
+    /// `None` on the first page. This is the cursor to save when walking `Direction::Backward`.
+    ///
+    pub fn previous_cursor(&self) -> Option<&str> {
+        self.pagination
+            .as_ref()
+            .and_then(|pagination| pagination.previous_ending_before.as_deref())
+    }
+}
+
+impl<T> IntoIterator for Page<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Page<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+///
+/// Query parameters accepted by every list endpoint.
+///
+/// ```no_run
+/// use coinbase_rs::pagination::{ListOptions, Order};
+/// use coinbase_rs::Private;
+///
+/// # fn run(client: Private, account: coinbase_rs::AccountId) {
+/// // The 10 most recent transactions, oldest first.
+/// let options = ListOptions::new().limit(10).order(Order::Ascending);
+/// let transactions = client.transactions(&account, &options);
+/// # }
+/// ```
+///
+/// The cursors are resource ids, as found in `Pagination::next_starting_after` and
+/// `Pagination::previous_ending_before`. To resume a stream, pass the last saved
+/// `Page::next_cursor` to `starting_after`. With the default descending order,
+/// `ending_before` with the newest id seen so far only fetches newer records.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    limit: Option<usize>,
+    order: Option<Order>,
+    starting_after: Option<String>,
+    ending_before: Option<String>,
+    pub(crate) direction: Direction,
+}
+
+impl ListOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Number of results per page, between 1 and `MAX_LIMIT`, values outside that range are
+    /// clamped. Defaults to `DEFAULT_LIMIT`.
+    ///
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Result order, the API defaults to `Order::Descending` (newest first).
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Only return results after the resource with this id, in the requested order.
+    pub fn starting_after(mut self, id: &str) -> Self {
+        self.starting_after = Some(id.to_string());
+        self
+    }
+
+    /// Only return results before the resource with this id, in the requested order.
+    pub fn ending_before(mut self, id: &str) -> Self {
+        self.ending_before = Some(id.to_string());
+        self
+    }
+
+    ///
+    /// Follow `previous_uri` instead of `next_uri`, e.g. to walk towards newer records from a
+    /// known id with `ending_before`.
+    ///
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub(crate) fn query(&self) -> Vec<(String, String)> {
+        let mut query = vec![(
+            "limit".to_string(),
+            self.limit
+                .unwrap_or(DEFAULT_LIMIT)
+                .clamp(1, MAX_LIMIT)
+                .to_string(),
+        )];
+        if let Some(order) = self.order {
+            query.push(("order".to_string(), order.as_str().to_string()));
+        }
+        if let Some(ref id) = self.starting_after {
+            query.push(("starting_after".to_string(), id.clone()));
+        }
+        if let Some(ref id) = self.ending_before {
+            query.push(("ending_before".to_string(), id.clone()));
+        }
+        query
+    }
+}
+
+///
+/// Adapters for the paginated streams returned by list endpoints.
+///
+pub trait PageStreamExt<P>: Stream<Item = Result<P>> + Sized
+where
+    P: IntoIterator,
+{
+    ///
+    /// Flatten a stream of pages into a stream of items.
+    ///
+    /// Pages are still fetched lazily: the next page is only requested once every item of the
+    /// current one has been consumed, so `take(n)` never fetches more pages than it needs. An
+    /// error fetching a page is yielded in place of its items.
+    ///
+    /// ```no_run
+    /// use coinbase_rs::{ListOptions, PageStreamExt, Public, MAIN_URL};
+    /// use futures::TryStreamExt;
+    ///
+    /// # async fn run() -> coinbase_rs::Result<()> {
+    /// let client = Public::new(MAIN_URL);
+    /// let currencies: Vec<_> = client.currencies(&ListOptions::new()).items().try_collect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    fn items(self) -> impl Stream<Item = Result<P::Item>> {
+        self.map_ok(|page| stream::iter(page).map(Ok)).try_flatten()
+    }
+}
+
+impl<S, P> PageStreamExt<P> for S
+where
+    S: Stream<Item = Result<P>>,
+    P: IntoIterator,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::{pin_mut, TryStreamExt};
+    use uritemplate::UriTemplate;
+
+    use super::*;
+    use crate::CBError;
+
+    #[test]
+    fn test_list_options_query() {
+        let query = |options: ListOptions| {
+            UriTemplate::new("/v2/accounts{?query*}")
+                .set("query", options.query())
+                .build()
+        };
+
+        assert_eq!(query(ListOptions::new()), "/v2/accounts?limit=100");
+        assert_eq!(query(ListOptions::new().limit(0)), "/v2/accounts?limit=1");
+        assert_eq!(
+            query(ListOptions::new().limit(1000)),
+            "/v2/accounts?limit=100"
+        );
+        assert_eq!(
+            query(
+                ListOptions::new()
+                    .limit(10)
+                    .order(Order::Ascending)
+                    .starting_after("d16ec1ba-b3f7-5d6a-a9c8-817930030324")
+                    .ending_before("LINK")
+            ),
+            "/v2/accounts?limit=10&order=asc&starting_after=d16ec1ba-b3f7-5d6a-a9c8-817930030324&ending_before=LINK"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_items_flattens_api_pages() {
+        let pagination: Pagination = serde_json::from_str(
+            r#"{"limit": 2, "order": "desc", "next_starting_after": "b", "next_uri": "/v2/accounts?starting_after=b"}"#,
+        )
+        .unwrap();
+        let first = Page {
+            data: vec!["a", "b"],
+            pagination: Some(pagination),
+        };
+        assert_eq!(first.next_cursor(), Some("b"));
+        assert_eq!((&first).into_iter().count(), 2);
+
+        let last = Page {
+            data: vec!["c"],
+            pagination: None,
+        };
+        assert_eq!(last.next_cursor(), None);
+
+        let pages = stream::iter(vec![Ok(first), Ok(last)]);
+        let items: Vec<&str> = pages.items().try_collect().await.unwrap();
+        assert_eq!(items, vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_items_flattens_pages() {
+        let pages = stream::iter(vec![Ok(vec![1, 2]), Ok(vec![]), Ok(vec![3])]);
+        let items: Vec<i32> = pages.items().try_collect().await.unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_items_fetches_pages_lazily() {
+        let fetched = AtomicUsize::new(0);
+        let pages = stream::iter(vec![vec![1, 2], vec![3, 4], vec![5, 6]]).map(|page| {
+            fetched.fetch_add(1, Ordering::SeqCst);
+            Ok(page)
+        });
+
+        let items: Vec<i32> = pages.items().take(3).try_collect().await.unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+        assert_eq!(fetched.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_items_yields_page_errors() {
+        let pages = stream::iter(vec![Ok(vec![1]), Err(CBError::Config("boom".to_string()))]);
+        let items = pages
+            .items()
+            .try_filter(|item| futures::future::ready(*item > 0));
+        pin_mut!(items);
+
+        assert_eq!(items.try_next().await.unwrap(), Some(1));
+        assert!(items.try_next().await.is_err());
+    }
+}
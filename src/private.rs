@@ -1,15 +1,38 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
 use bigdecimal::BigDecimal;
-use futures::stream::Stream;
-use hyper::Uri;
+use futures::future::{self, Either};
+use futures::stream::{self, Stream, StreamExt};
+use http::Method;
 use uritemplate::UriTemplate;
 use uuid::Uuid;
 
-use crate::{public::Public, request, DateTime, Result};
+use crate::{
+    builder::ClientBuilder,
+    error::ValidationError,
+    metrics::Metrics,
+    pagination::{ListOptions, Page, PageStreamExt},
+    public::Public,
+    rate_limit::RateLimiter,
+    request,
+    retry::RetryPolicy,
+    transport::Transport,
+    CBError, DateTime, Result,
+};
+
+pub use crate::pagination::{Order, Pagination};
 
+///
+/// A client for the authenticated API. Clones share the transport, rate limiter and metrics.
+///
+#[derive(Clone)]
 pub struct Private {
     _pub: Public,
     key: String,
     secret: String,
+    two_factor_token: Option<String>,
 }
 
 impl Private {
@@ -18,9 +41,143 @@ impl Private {
             _pub: Public::new(uri),
             key: key.to_string(),
             secret: secret.to_string(),
+            two_factor_token: None,
+        }
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    pub(crate) fn from_parts(public: Public, key: &str, secret: &str) -> Self {
+        Self {
+            _pub: public,
+            key: key.to_string(),
+            secret: secret.to_string(),
+            two_factor_token: None,
+        }
+    }
+
+    ///
+    /// Create a client that sends its requests through `transport` instead of the default hyper
+    /// client.
+    ///
+    pub fn with_transport<T>(uri: &str, key: &str, secret: &str, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self {
+            _pub: Public::with_transport(uri, transport),
+            key: key.to_string(),
+            secret: secret.to_string(),
+            two_factor_token: None,
         }
     }
 
+    ///
+    /// Replace the default rate limiter, e.g. to share one budget between several clients.
+    ///
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self._pub = self._pub.with_rate_limiter(rate_limiter);
+        self
+    }
+
+    ///
+    /// Replace the default policy for retrying connection errors, 429 and 5xx responses.
+    ///
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self._pub = self._pub.with_retry_policy(retry_policy);
+        self
+    }
+
+    ///
+    /// Report the outcome of every call to `metrics`.
+    ///
+    pub fn with_metrics<M>(mut self, metrics: M) -> Self
+    where
+        M: Metrics + 'static,
+    {
+        self._pub = self._pub.with_metrics(metrics);
+        self
+    }
+
+    ///
+    /// A copy of this client sending `token` as `CB-2FA-TOKEN` with every request, e.g. to send
+    /// money with a code the user already entered. See `call_with_two_factor` to ask for the code
+    /// only when the API requires it.
+    ///
+    pub fn with_two_factor_token(&self, token: impl Into<String>) -> Private {
+        let mut client = self.clone();
+        client.two_factor_token = Some(token.into());
+        client
+    }
+
+    ///
+    /// The unauthenticated client used by this client, sharing its rate limiter.
+    ///
+    pub fn public(&self) -> &Public {
+        &self._pub
+    }
+
+    ///
+    /// **Show current user**
+    ///
+    /// Get current user’s public information. To get user’s email or private information, use
+    /// permissions `wallet:user:email` and `wallet:user:read`.
+    ///
+    /// https://developers.coinbase.com/api/v2#show-current-user
+    ///
+    pub async fn current_user(&self) -> Result<User> {
+        self.get("/v2/user", "/v2/user").await
+    }
+
+    ///
+    /// **Show a user**
+    ///
+    /// Get any user’s public information with their ID.
+    ///
+    /// https://developers.coinbase.com/api/v2#show-a-user
+    ///
+    pub async fn user(&self, user_id: &Uuid) -> Result<User> {
+        let uri = UriTemplate::new("/v2/users/{user}")
+            .set("user", user_id.to_string())
+            .build();
+        self.get("/v2/users/{user}", &uri).await
+    }
+
+    ///
+    /// **Show authorization information**
+    ///
+    /// Get current user’s authorization information including granted scopes and send limits
+    /// when using OAuth2 authentication.
+    ///
+    /// https://developers.coinbase.com/api/v2#show-authorization-information
+    ///
+    pub async fn auth_info(&self) -> Result<AuthInfo> {
+        self.get("/v2/user/auth", "/v2/user/auth").await
+    }
+
+    ///
+    /// **Update current user**
+    ///
+    /// Modify current user and their preferences. Fields left as `None` are unchanged.
+    ///
+    /// https://developers.coinbase.com/api/v2#update-current-user
+    ///
+    pub async fn update_current_user(
+        &self,
+        name: Option<&str>,
+        time_zone: Option<&str>,
+        native_currency: Option<&str>,
+    ) -> Result<User> {
+        let body = UserUpdate {
+            name,
+            time_zone,
+            native_currency,
+        };
+        self.send(Method::PUT, "/v2/user", "/v2/user", &body).await
+    }
+
     ///
     /// **List accounts**
     ///
@@ -28,13 +185,85 @@ impl Private {
     ///
     /// https://developers.coinbase.com/api/v2#list-accounts
     ///
-    pub fn accounts<'a>(&'a self) -> impl Stream<Item = Result<Vec<Account>>> + 'a {
-        let limit = 100;
+    pub fn accounts<'a>(
+        &'a self,
+        options: &ListOptions,
+    ) -> impl Stream<Item = Result<Page<Account>>> + 'a {
         let uri = UriTemplate::new("/v2/accounts{?query*}")
-            .set("query", &[("limit", limit.to_string().as_ref())])
+            .set("query", options.query())
+            .build();
+        self._pub
+            .get_stream(self.request("/v2/accounts", &uri), options.direction)
+    }
+
+    ///
+    /// **Show an account**
+    ///
+    /// Show current user’s account. To access the primary account for a given currency, a
+    /// currency string (BTC or ETH) can be used instead of the account id in the URL.
+    ///
+    /// https://developers.coinbase.com/api/v2#show-an-account
+    ///
+    pub async fn account(&self, account_id: &AccountId) -> Result<Account> {
+        let uri = UriTemplate::new("/v2/accounts/{account}")
+            .set("account", account_id.to_string())
+            .build();
+        self.get("/v2/accounts/{account}", &uri).await
+    }
+
+    ///
+    /// **Update account**
+    ///
+    /// Modifies user’s account name.
+    ///
+    /// https://developers.coinbase.com/api/v2#update-account
+    ///
+    pub async fn update_account(&self, account_id: &AccountId, name: &str) -> Result<Account> {
+        let uri = UriTemplate::new("/v2/accounts/{account}")
+            .set("account", account_id.to_string())
+            .build();
+        let body = AccountUpdate { name };
+        self.send(Method::PUT, "/v2/accounts/{account}", &uri, &body)
+            .await
+    }
+
+    ///
+    /// **Set account as primary**
+    ///
+    /// Promote an account as primary account.
+    ///
+    /// https://developers.coinbase.com/api/v2#set-account-as-primary
+    ///
+    pub async fn set_primary_account(&self, account_id: &AccountId) -> Result<Account> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/primary")
+            .set("account", account_id.to_string())
+            .build();
+        let request = self
+            .request("/v2/accounts/{account}/primary", &uri)?
+            .method(Method::POST);
+        self.execute(request).await
+    }
+
+    ///
+    /// **Delete account**
+    ///
+    /// Removes user’s account. In order to remove an account it can’t be:
+    ///
+    /// - Primary account
+    /// - Account with non-zero balance
+    /// - Fiat account
+    /// - Vault with a pending withdrawal
+    ///
+    /// https://developers.coinbase.com/api/v2#delete-account
+    ///
+    pub async fn delete_account(&self, account_id: &AccountId) -> Result<()> {
+        let uri = UriTemplate::new("/v2/accounts/{account}")
+            .set("account", account_id.to_string())
             .build();
-        let request = self.request(&uri);
-        self._pub.get_stream(request)
+        let request = self
+            .request("/v2/accounts/{account}", &uri)?
+            .method(Method::DELETE);
+        self._pub.make_empty_request(request).await
     }
 
     ///
@@ -46,15 +275,466 @@ impl Private {
     ///
     pub fn transactions<'a>(
         &'a self,
-        account_id: &Uuid,
-    ) -> impl Stream<Item = Result<Vec<Transaction>>> + 'a {
-        let limit = 100;
+        account_id: &AccountId,
+        options: &ListOptions,
+    ) -> impl Stream<Item = Result<Page<Transaction>>> + 'a {
         let uri = UriTemplate::new("/v2/accounts/{account}/transactions{?query*}")
             .set("account", account_id.to_string())
-            .set("query", &[("limit", limit.to_string().as_ref())])
+            .set("query", options.query())
+            .build();
+        self._pub.get_stream(
+            self.request("/v2/accounts/{account}/transactions", &uri),
+            options.direction,
+        )
+    }
+
+    ///
+    /// **Show a transaction**
+    ///
+    /// Show an individual transaction for an account.
+    ///
+    /// https://developers.coinbase.com/api/v2#show-a-transaction
+    ///
+    pub async fn transaction(
+        &self,
+        account_id: &AccountId,
+        transaction_id: &Uuid,
+    ) -> Result<Transaction> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/transactions/{transaction}")
+            .set("account", account_id.to_string())
+            .set("transaction", transaction_id.to_string())
+            .build();
+        self.get("/v2/accounts/{account}/transactions/{transaction}", &uri)
+            .await
+    }
+
+    ///
+    /// Poll a transaction every `interval` until its status leaves `pending`, and return it.
+    ///
+    /// Fails with `CBError::Timeout` if it is still pending after `timeout`, and with the first
+    /// error fetching it otherwise.
+    ///
+    pub async fn wait_for_transaction(
+        &self,
+        account_id: &AccountId,
+        transaction_id: &Uuid,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<Transaction> {
+        let poll = async {
+            loop {
+                let transaction = self.transaction(account_id, transaction_id).await?;
+                if !transaction.is_pending() {
+                    return Ok(transaction);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        };
+        tokio::time::timeout(timeout, poll)
+            .await
+            .map_err(|_| CBError::Timeout(timeout))?
+    }
+
+    ///
+    /// **Send money**
+    ///
+    /// Send funds to a bitcoin address, email address or another account. The request's `idem`
+    /// token makes the call safe to retry: the API never creates two sends with the same token.
+    ///
+    /// ```no_run
+    /// use coinbase_rs::{private::SendRequest, AccountId, Private};
+    ///
+    /// # async fn run(client: Private) -> coinbase_rs::Result<()> {
+    /// let send = SendRequest::new("1AUJ8z5RuHRTqD1eikyfUUetzGmdWLGkpT", "0.1".parse().unwrap(), "BTC")
+    ///     .description("Invoice 1234");
+    /// let transaction = client.send_money(&AccountId::from("BTC"), &send).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// https://developers.coinbase.com/api/v2#send-money
+    ///
+    pub async fn send_money(
+        &self,
+        account_id: &AccountId,
+        send: &SendRequest,
+    ) -> Result<Transaction> {
+        self.execute(self.send_request(account_id, send)?).await
+    }
+
+    ///
+    /// Send money, asking `two_factor` for a token if the API requires two factor
+    /// authentication, e.g. by prompting the user for the code they just received. The send is
+    /// then replayed with the same `idem`, so it's never sent twice. Returning `None` from
+    /// `two_factor` gives up with the `two_factor_required` error.
+    ///
+    /// ```no_run
+    /// use coinbase_rs::{private::SendRequest, AccountId, Private};
+    ///
+    /// # async fn prompt() -> String { unimplemented!() }
+    /// # async fn run(client: Private, send: SendRequest) -> coinbase_rs::Result<()> {
+    /// let transaction = client
+    ///     .send_money_with_two_factor(&AccountId::from("BTC"), &send, || async {
+    ///         Some(prompt().await)
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub async fn send_money_with_two_factor<F, Fut>(
+        &self,
+        account_id: &AccountId,
+        send: &SendRequest,
+        two_factor: F,
+    ) -> Result<Transaction>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Option<String>>,
+    {
+        self.call_with_two_factor(
+            |client| async move { client.send_money(account_id, send).await },
+            two_factor,
+        )
+        .await
+    }
+
+    ///
+    /// Make any call that may require two factor authentication, e.g. `transfer_money` or
+    /// `commit_buy`, asking `two_factor` for a token if the API rejects it with
+    /// `two_factor_required`. `call` is then replayed on a client sending the token as
+    /// `CB-2FA-TOKEN` with every request. Returning `None` from `two_factor` gives up with the
+    /// `two_factor_required` error.
+    ///
+    /// A rejected call wasn't carried out, but pass an explicit `idem` where the call takes one,
+    /// so the replay is deduplicated against the first attempt all the same.
+    ///
+    /// ```no_run
+    /// use coinbase_rs::{AccountId, Private};
+    ///
+    /// # async fn prompt() -> String { unimplemented!() }
+    /// # async fn run(client: Private, from: AccountId, to: AccountId) -> coinbase_rs::Result<()> {
+    /// let amount = "0.1".parse().unwrap();
+    /// let transaction = client
+    ///     .call_with_two_factor(
+    ///         |client| {
+    ///             let (from, to, amount) = (&from, &to, &amount);
+    ///             async move {
+    ///                 client
+    ///                     .transfer_money(from, to, amount, "BTC", Some("sweep-1234"))
+    ///                     .await
+    ///             }
+    ///         },
+    ///         || async { Some(prompt().await) },
+    ///     )
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub async fn call_with_two_factor<T, C, CFut, F, Fut>(
+        &self,
+        call: C,
+        two_factor: F,
+    ) -> Result<T>
+    where
+        C: Fn(Private) -> CFut,
+        CFut: Future<Output = Result<T>>,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Option<String>>,
+    {
+        match call(self.clone()).await {
+            Err(e) if e.is_two_factor_required() => match two_factor().await {
+                Some(token) => call(self.with_two_factor_token(token)).await,
+                None => Err(e),
+            },
+            result => result,
+        }
+    }
+
+    ///
+    /// **Transfer money between accounts**
+    ///
+    /// Transfer `amount` of `currency` between two of the user's own accounts, e.g. from a vault
+    /// to the primary wallet. A random `idem` token is generated when none is given.
+    ///
+    /// Both accounts are fetched first, and the transfer fails with a `ValidationError`, without
+    /// being sent, if they're the same account, either doesn't hold `currency` or the source
+    /// balance is too low. The currency code is case insensitive.
+    ///
+    /// https://developers.coinbase.com/api/v2#transfer-money-between-accounts
+    ///
+    pub async fn transfer_money(
+        &self,
+        from_account: &AccountId,
+        to_account: &AccountId,
+        amount: &BigDecimal,
+        currency: &str,
+        idem: Option<&str>,
+    ) -> Result<Transaction> {
+        let currency = &currency.to_uppercase();
+        let source = self.account(from_account).await?;
+        let destination = self.account(to_account).await?;
+        if source.id == destination.id {
+            return Err(ValidationError::SameAccount {
+                account: source.id.to_string(),
+            }
+            .into());
+        }
+        for account in &[&source, &destination] {
+            if !account.currency.code.eq_ignore_ascii_case(currency) {
+                return Err(ValidationError::CurrencyMismatch {
+                    account: account.id.to_string(),
+                    account_currency: account.currency.code.clone(),
+                    currency: currency.to_string(),
+                }
+                .into());
+            }
+        }
+        if source.balance.amount < *amount {
+            return Err(ValidationError::InsufficientFunds {
+                available: source.balance.amount,
+                requested: amount.clone(),
+                currency: currency.to_string(),
+            }
+            .into());
+        }
+
+        let idem = idem
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let body = TransactionCreate {
+            r#type: "transfer",
+            params: &TransferParams {
+                to: &destination.id,
+                amount,
+                currency,
+                idem: &idem,
+            },
+        };
+        self.execute(self.transaction_request(from_account, &body, true)?)
+            .await
+    }
+
+    ///
+    /// **Request money**
+    ///
+    /// Requests money from an email address. The request is a transaction of type `request`,
+    /// pending until the recipient pays it or it's cancelled.
+    ///
+    /// https://developers.coinbase.com/api/v2#request-money
+    ///
+    pub async fn request_money(
+        &self,
+        account_id: &AccountId,
+        request: &MoneyRequest,
+    ) -> Result<Transaction> {
+        let body = TransactionCreate {
+            r#type: "request",
+            params: request,
+        };
+        self.execute(self.transaction_request(account_id, &body, false)?)
+            .await
+    }
+
+    ///
+    /// **Resend request money**
+    ///
+    /// Lets the recipient of a money request know that the request is still outstanding.
+    ///
+    /// https://developers.coinbase.com/api/v2#resend-request-money
+    ///
+    pub async fn resend_money_request(
+        &self,
+        account_id: &AccountId,
+        transaction_id: &Uuid,
+    ) -> Result<()> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/transactions/{transaction}/resend")
+            .set("account", account_id.to_string())
+            .set("transaction", transaction_id.to_string())
             .build();
-        let request = self.request(&uri);
-        self._pub.get_stream(request)
+        let request = self
+            .request(
+                "/v2/accounts/{account}/transactions/{transaction}/resend",
+                &uri,
+            )?
+            .method(Method::POST);
+        self._pub.make_empty_request(request).await
+    }
+
+    ///
+    /// **Complete request money**
+    ///
+    /// Lets the user pay a money request they received. Funds leave the account, so the API may
+    /// ask for two factor authentication, see `call_with_two_factor`.
+    ///
+    /// https://developers.coinbase.com/api/v2#complete-request-money
+    ///
+    pub async fn complete_money_request(
+        &self,
+        account_id: &AccountId,
+        transaction_id: &Uuid,
+    ) -> Result<Transaction> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/transactions/{transaction}/complete")
+            .set("account", account_id.to_string())
+            .set("transaction", transaction_id.to_string())
+            .build();
+        let request = self
+            .request(
+                "/v2/accounts/{account}/transactions/{transaction}/complete",
+                &uri,
+            )?
+            .method(Method::POST);
+        self.execute(request).await
+    }
+
+    ///
+    /// **Cancel request money**
+    ///
+    /// Lets the user cancel a money request. Money requests can be canceled by the sender or
+    /// the recipient.
+    ///
+    /// https://developers.coinbase.com/api/v2#cancel-request-money
+    ///
+    pub async fn cancel_money_request(
+        &self,
+        account_id: &AccountId,
+        transaction_id: &Uuid,
+    ) -> Result<()> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/transactions/{transaction}")
+            .set("account", account_id.to_string())
+            .set("transaction", transaction_id.to_string())
+            .build();
+        let request = self
+            .request("/v2/accounts/{account}/transactions/{transaction}", &uri)?
+            .method(Method::DELETE);
+        self._pub.make_empty_request(request).await
+    }
+
+    ///
+    /// **List buys**
+    ///
+    /// Lists buys for an account.
+    ///
+    /// https://developers.coinbase.com/api/v2#list-buys
+    ///
+    pub fn buys<'a>(
+        &'a self,
+        account_id: &AccountId,
+        options: &ListOptions,
+    ) -> impl Stream<Item = Result<Page<Buy>>> + 'a {
+        let uri = UriTemplate::new("/v2/accounts/{account}/buys{?query*}")
+            .set("account", account_id.to_string())
+            .set("query", options.query())
+            .build();
+        self._pub.get_stream(
+            self.request("/v2/accounts/{account}/buys", &uri),
+            options.direction,
+        )
+    }
+
+    ///
+    /// **Show a buy**
+    ///
+    /// Show an individual buy.
+    ///
+    /// https://developers.coinbase.com/api/v2#show-a-buy
+    ///
+    pub async fn buy(&self, account_id: &AccountId, buy_id: &Uuid) -> Result<Buy> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/buys/{buy}")
+            .set("account", account_id.to_string())
+            .set("buy", buy_id.to_string())
+            .build();
+        self.get("/v2/accounts/{account}/buys/{buy}", &uri).await
+    }
+
+    ///
+    /// **Place buy order**
+    ///
+    /// Buys a user-defined amount of bitcoin, bitcoin cash, litecoin or ethereum, paid with
+    /// `payment_method`, or the primary buy method when `None`.
+    ///
+    /// With `quote`, nothing is bought: the returned `Buy` only previews the fees and total.
+    /// Without `commit`, the buy is created but has to be completed with `commit_buy`, e.g. after
+    /// the user accepted the price.
+    ///
+    /// https://developers.coinbase.com/api/v2#place-buy-order
+    ///
+    pub async fn place_buy_order(
+        &self,
+        account_id: &AccountId,
+        amount: BuyAmount,
+        currency: &str,
+        payment_method: Option<&str>,
+        commit: bool,
+        quote: bool,
+    ) -> Result<Buy> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/buys")
+            .set("account", account_id.to_string())
+            .build();
+        let body = BuyOrder {
+            amount,
+            currency,
+            payment_method,
+            commit,
+            quote,
+        };
+        self.send(Method::POST, "/v2/accounts/{account}/buys", &uri, &body)
+            .await
+    }
+
+    ///
+    /// **Commit a buy**
+    ///
+    /// Completes a buy that was created with `commit` set to false.
+    ///
+    /// https://developers.coinbase.com/api/v2#commit-a-buy
+    ///
+    pub async fn commit_buy(&self, account_id: &AccountId, buy_id: &Uuid) -> Result<Buy> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/buys/{buy}/commit")
+            .set("account", account_id.to_string())
+            .set("buy", buy_id.to_string())
+            .build();
+        let request = self
+            .request("/v2/accounts/{account}/buys/{buy}/commit", &uri)?
+            .method(Method::POST);
+        self.execute(request).await
+    }
+
+    ///
+    /// Every transaction of every account, as one stream of `(account, transaction)` pairs.
+    ///
+    /// Up to `concurrency` accounts are paged through at once, all sharing the client's rate
+    /// limiter. Results from different accounts are interleaved, `options` applies to each
+    /// account's transactions. An error fetching one account's transactions is yielded in place
+    /// of them, along with that account, without aborting the other accounts.
+    ///
+    pub fn all_transactions<'a>(
+        &'a self,
+        options: &ListOptions,
+        concurrency: usize,
+    ) -> impl Stream<Item = std::result::Result<(Account, Transaction), AccountError>> + 'a {
+        let options = options.clone();
+        self.accounts(&ListOptions::new())
+            .items()
+            .map(move |account| match account {
+                Ok(account) => Either::Left(Box::pin(
+                    self.transactions(&account.id, &options)
+                        .items()
+                        .map(move |transaction| match transaction {
+                            Ok(transaction) => Ok((account.clone(), transaction)),
+                            Err(error) => Err(AccountError {
+                                account: Some(Box::new(account.clone())),
+                                error,
+                            }),
+                        }),
+                )),
+                Err(error) => Either::Right(stream::once(future::ready(Err(AccountError {
+                    account: None,
+                    error,
+                })))),
+            })
+            .flatten_unordered(concurrency.max(1))
     }
 
     ///
@@ -66,25 +746,243 @@ impl Private {
     ///
     pub fn list_addresses<'a>(
         &'a self,
-        account_id: &Uuid,
-    ) -> impl Stream<Item = Result<Vec<Address>>> + 'a {
+        account_id: &AccountId,
+        options: &ListOptions,
+    ) -> impl Stream<Item = Result<Page<Address>>> + 'a {
+        let uri = UriTemplate::new("/v2/accounts/{account}/addresses{?query*}")
+            .set("account", account_id.to_string())
+            .set("query", options.query())
+            .build();
+        self._pub.get_stream(
+            self.request("/v2/accounts/{account}/addresses", &uri),
+            options.direction,
+        )
+    }
+
+    ///
+    /// **Show address**
+    ///
+    /// Show an individual address for an account. A regular cryptocurrency address can be used
+    /// in place of the address id.
+    ///
+    /// https://docs.cloud.coinbase.com/sign-in-with-coinbase/docs/api-addresses#show-address
+    ///
+    pub async fn address(&self, account_id: &AccountId, address_id: &str) -> Result<Address> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/addresses/{address}")
+            .set("account", account_id.to_string())
+            .set("address", address_id)
+            .build();
+        self.get("/v2/accounts/{account}/addresses/{address}", &uri)
+            .await
+    }
+
+    ///
+    /// **List address’s transactions**
+    ///
+    /// List transactions that have been sent to a specific address. A regular cryptocurrency
+    /// address can be used in place of the address id.
+    ///
+    /// https://docs.cloud.coinbase.com/sign-in-with-coinbase/docs/api-addresses#list-transactions
+    ///
+    pub fn address_transactions<'a>(
+        &'a self,
+        account_id: &AccountId,
+        address_id: &str,
+        options: &ListOptions,
+    ) -> impl Stream<Item = Result<Page<Transaction>>> + 'a {
+        let uri =
+            UriTemplate::new("/v2/accounts/{account}/addresses/{address}/transactions{?query*}")
+                .set("account", account_id.to_string())
+                .set("address", address_id)
+                .set("query", options.query())
+                .build();
+        self._pub.get_stream(
+            self.request(
+                "/v2/accounts/{account}/addresses/{address}/transactions",
+                &uri,
+            ),
+            options.direction,
+        )
+    }
+
+    ///
+    /// **Create address**
+    ///
+    /// Creates a new address for an account. Addresses can be used to receive funds, a new one
+    /// can be generated for each payment.
+    ///
+    /// https://docs.cloud.coinbase.com/sign-in-with-coinbase/docs/api-addresses#create-address
+    ///
+    pub async fn create_address(
+        &self,
+        account_id: &AccountId,
+        name: Option<&str>,
+    ) -> Result<Address> {
         let uri = UriTemplate::new("/v2/accounts/{account}/addresses")
             .set("account", account_id.to_string())
             .build();
-        let request = self.request(&uri);
-        self._pub.get_stream(request)
+        let body = AddressCreate { name };
+        self.send(
+            Method::POST,
+            "/v2/accounts/{account}/addresses",
+            &uri,
+            &body,
+        )
+        .await
+    }
+
+    async fn get<U>(&self, endpoint: &'static str, uri: &str) -> Result<U>
+    where
+        U: serde::de::DeserializeOwned,
+    {
+        self.execute(self.request(endpoint, uri)?).await
+    }
+
+    /// Send a signed request with `body` as its JSON payload.
+    async fn send<U, B>(
+        &self,
+        method: Method,
+        endpoint: &'static str,
+        uri: &str,
+        body: &B,
+    ) -> Result<U>
+    where
+        U: serde::de::DeserializeOwned,
+        B: serde::Serialize,
+    {
+        self.execute(self.json_request(method, endpoint, uri, body)?)
+            .await
+    }
+
+    ///
+    /// Create a transaction (send, transfer or request) on an account. Set `retry_safe` when the
+    /// body carries an `idem` token, the server won't create a second transaction with it.
+    ///
+    fn transaction_request<B>(
+        &self,
+        account_id: &AccountId,
+        body: &B,
+        retry_safe: bool,
+    ) -> Result<request::Builder>
+    where
+        B: serde::Serialize,
+    {
+        let uri = UriTemplate::new("/v2/accounts/{account}/transactions")
+            .set("account", account_id.to_string())
+            .build();
+        let request = self.json_request(
+            Method::POST,
+            "/v2/accounts/{account}/transactions",
+            &uri,
+            body,
+        )?;
+        Ok(if retry_safe {
+            request.retry_safe()
+        } else {
+            request
+        })
+    }
+
+    fn send_request(&self, account_id: &AccountId, send: &SendRequest) -> Result<request::Builder> {
+        let body = TransactionCreate {
+            r#type: "send",
+            params: send,
+        };
+        self.transaction_request(account_id, &body, true)
+    }
+
+    async fn execute<U>(&self, request: request::Builder) -> Result<U>
+    where
+        U: serde::de::DeserializeOwned,
+    {
+        let result = self._pub.make_request(request).await?;
+        Ok(result.data)
     }
 
-    fn request(&self, _uri: &str) -> request::Builder {
-        let uri: Uri = (self._pub.uri.to_string() + _uri).parse().unwrap();
-        request::Builder::new_with_auth(&self.key, &self.secret).uri(uri)
+    fn json_request<B>(
+        &self,
+        method: Method,
+        endpoint: &'static str,
+        uri: &str,
+        body: &B,
+    ) -> Result<request::Builder>
+    where
+        B: serde::Serialize,
+    {
+        let body = serde_json::to_vec(body)?;
+        Ok(self.request(endpoint, uri)?.method(method).body(&body))
+    }
+
+    fn request(&self, endpoint: &'static str, uri: &str) -> Result<request::Builder> {
+        let uri = request::join(&self._pub.uri, uri)?;
+        let mut request = request::Builder::new_with_auth(&self.key, &self.secret)
+            .uri(uri)
+            .endpoint(endpoint);
+        if let Some(ref token) = self.two_factor_token {
+            request = request.two_factor_token(token);
+        }
+        Ok(self._pub.prepare(request))
+    }
+}
+
+///
+/// An account id. Most accounts are identified by a UUID, but some token wallets are identified
+/// by their currency code instead, e.g. `LINK`.
+///
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum AccountId {
+    Uuid(Uuid),
+    Name(String),
+}
+
+impl std::convert::From<Uuid> for AccountId {
+    fn from(id: Uuid) -> Self {
+        AccountId::Uuid(id)
+    }
+}
+
+impl std::convert::From<String> for AccountId {
+    fn from(id: String) -> Self {
+        match Uuid::parse_str(&id) {
+            Ok(uuid) => AccountId::Uuid(uuid),
+            Err(_) => AccountId::Name(id),
+        }
+    }
+}
+
+impl std::convert::From<&str> for AccountId {
+    fn from(id: &str) -> Self {
+        id.to_string().into()
+    }
+}
+
+impl std::convert::From<AccountId> for String {
+    fn from(id: AccountId) -> Self {
+        id.to_string()
+    }
+}
+
+impl std::str::FromStr for AccountId {
+    type Err = std::convert::Infallible;
+
+    fn from_str(id: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(id.into())
+    }
+}
+
+impl std::fmt::Display for AccountId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AccountId::Uuid(id) => write!(f, "{}", id),
+            AccountId::Name(name) => write!(f, "{}", name),
+        }
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct Account {
-    // id appears to be either a UUID or a token name e.g: "LINK"
-    pub id: String,
+    pub id: AccountId,
 
     pub r#type: String,
 
@@ -105,13 +1003,43 @@ pub struct Account {
     pub allow_withdrawals: bool,
 }
 
-#[derive(Deserialize, Debug)]
+///
+/// An error from `Private::all_transactions`, along with the account it belongs to so it can be
+/// retried or reported on its own.
+///
+#[derive(Debug)]
+pub struct AccountError {
+    /// The account whose transactions failed, `None` when listing the accounts failed.
+    pub account: Option<Box<Account>>,
+    pub error: CBError,
+}
+
+impl std::fmt::Display for AccountError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.account {
+            Some(ref account) => write!(
+                f,
+                "listing transactions of account {}: {}",
+                account.id, self.error
+            ),
+            None => write!(f, "listing accounts: {}", self.error),
+        }
+    }
+}
+
+impl std::error::Error for AccountError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct Balance {
     pub amount: BigDecimal,
     pub currency: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct Address {
     pub id: String,
     pub address: String,
@@ -121,9 +1049,35 @@ pub struct Address {
     pub network: String,
     pub resource: String,
     pub resource_path: String,
+
+    /// The scheme of `deposit_uri`, e.g. `bitcoin`.
+    pub uri_scheme: Option<String>,
+    /// A payment URI for the address, e.g. `bitcoin:3Hjg...`.
+    pub deposit_uri: Option<String>,
+    pub address_info: Option<AddressInfo>,
+    pub destination_tag: Option<String>,
+    pub legacy_address: Option<String>,
+    pub callback_url: Option<String>,
+    pub exchange_deposit_address: Option<bool>,
+    /// Notices to show the user before they deposit to this address.
+    #[serde(default)]
+    pub warnings: Vec<AddressWarning>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AddressInfo {
+    pub address: String,
+    pub destination_tag: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
+pub struct AddressWarning {
+    pub title: String,
+    pub details: Option<String>,
+    pub image_url: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct Transaction {
     pub id: Uuid,
 
@@ -139,15 +1093,26 @@ pub struct Transaction {
     pub instant_exchange: bool,
     pub network: Option<Network>,
     pub from: Option<From>,
+    pub to: Option<To>,
+    pub description: Option<String>,
     pub details: TransactionDetails,
+
+    /// When an outstanding money request expires.
+    pub expires_at: Option<DateTime>,
 }
 
-#[derive(Deserialize, Debug)]
+impl Transaction {
+    pub fn is_pending(&self) -> bool {
+        self.status == "pending"
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct Network {
     pub status: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct From {
     pub id: Option<Uuid>,
     pub resource: String,
@@ -155,13 +1120,27 @@ pub struct From {
     pub currency: String,
 }
 
-#[derive(Deserialize, Debug)]
+///
+/// The recipient of a transaction: a user, an account, a crypto address or, for money requests,
+/// an email address.
+///
+#[derive(Deserialize, Clone, Debug)]
+pub struct To {
+    pub id: Option<Uuid>,
+    pub resource: String,
+    pub resource_path: Option<String>,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    pub currency: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct TransactionDetails {
     pub title: String,
     pub subtitle: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct Currency {
     pub code: String,
     pub name: String,
@@ -175,24 +1154,252 @@ pub struct Currency {
     pub destination_tag_regex: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Eq, PartialEq)]
-pub enum Order {
-    #[serde(rename = "asc")]
-    Ascending,
-    #[serde(rename = "desc")]
-    Descending,
+#[derive(Deserialize, Clone, Debug)]
+pub struct Buy {
+    pub id: Uuid,
+    /// `created`, `completed` or `canceled`.
+    pub status: String,
+    pub payment_method: Option<Resource>,
+    /// The transaction crediting the account, once committed.
+    pub transaction: Option<Resource>,
+    /// Amount of the bought currency.
+    pub amount: Balance,
+    /// Price paid, fees included.
+    pub total: Balance,
+    /// Price paid, fees excluded.
+    pub subtotal: Balance,
+    pub fee: Balance,
+    pub unit_price: Option<Balance>,
+    pub created_at: Option<DateTime>,
+    pub updated_at: Option<DateTime>,
+    pub resource: String,
+    pub resource_path: String,
+    pub committed: bool,
+    pub instant: bool,
+    /// When the bought funds are credited to the account.
+    pub payout_at: Option<DateTime>,
+    pub requires_completion_step: Option<bool>,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct Pagination {
-    pub ending_before: Option<DateTime>,
-    pub starting_after: Option<DateTime>,
-    pub previous_ending_before: Option<String>,
-    pub next_starting_after: Option<String>,
-    pub limit: usize,
-    pub order: Order,
-    pub previous_uri: Option<String>,
-    pub next_uri: Option<String>,
+///
+/// A reference to another API resource.
+///
+#[derive(Deserialize, Clone, Debug)]
+pub struct Resource {
+    pub id: Uuid,
+    pub resource: String,
+    pub resource_path: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct User {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub username: Option<String>,
+    pub profile_location: Option<String>,
+    pub profile_bio: Option<String>,
+    pub profile_url: Option<String>,
+    pub avatar_url: Option<String>,
+    pub resource: String,
+    pub resource_path: String,
+
+    // Only returned for the current user, depending on the granted scopes.
+    pub email: Option<String>,
+    pub time_zone: Option<String>,
+    pub native_currency: Option<String>,
+    pub bitcoin_unit: Option<String>,
+    pub state: Option<String>,
+    pub country: Option<Country>,
+    pub created_at: Option<DateTime>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Country {
+    pub code: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AuthInfo {
+    /// `oauth` or `api_key`.
+    pub method: String,
+    pub scopes: Vec<String>,
+    pub oauth_meta: Option<OAuthMeta>,
+}
+
+impl AuthInfo {
+    /// Whether `scope`, e.g. `wallet:transactions:send`, was granted.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|granted| granted == scope)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct OAuthMeta {
+    pub send_limit_amount: Option<BigDecimal>,
+    pub send_limit_currency: Option<String>,
+    pub send_limit_period: Option<String>,
+}
+
+///
+/// Parameters for `Private::send_money`.
+///
+/// A random `idem` token is generated for every new request. Reuse the same `SendRequest`, or
+/// pass the saved token to `idem`, when retrying a send whose outcome is unknown.
+///
+#[derive(Serialize, Clone, Debug)]
+pub struct SendRequest {
+    to: String,
+    amount: BigDecimal,
+    currency: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skip_notifications: Option<bool>,
+    idem: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    network: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    destination_tag: Option<String>,
+}
+
+impl SendRequest {
+    /// Send `amount` of `currency` to a crypto address or an email address.
+    pub fn new(to: &str, amount: BigDecimal, currency: &str) -> Self {
+        Self {
+            to: to.to_string(),
+            amount,
+            currency: currency.to_string(),
+            description: None,
+            skip_notifications: None,
+            idem: Uuid::new_v4().to_string(),
+            network: None,
+            destination_tag: None,
+        }
+    }
+
+    /// A note included in the email sent to the recipient.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Don't send notification emails for small amounts, e.g. for tipping.
+    pub fn skip_notifications(mut self, skip: bool) -> Self {
+        self.skip_notifications = Some(skip);
+        self
+    }
+
+    /// Replace the generated idempotency token, max 100 characters.
+    pub fn idem(mut self, idem: &str) -> Self {
+        self.idem = idem.to_string();
+        self
+    }
+
+    /// The network to send on when the currency is available on several, e.g. `ethereum`.
+    pub fn network(mut self, network: &str) -> Self {
+        self.network = Some(network.to_string());
+        self
+    }
+
+    /// The destination tag or memo required by some currencies, e.g. XRP or XLM.
+    pub fn destination_tag(mut self, destination_tag: &str) -> Self {
+        self.destination_tag = Some(destination_tag.to_string());
+        self
+    }
+
+    /// The idempotency token sent with this request, to save before sending.
+    pub fn idem_token(&self) -> &str {
+        &self.idem
+    }
+}
+
+///
+/// How much to buy: an `Amount` of the bought currency, or a `Total` to pay in the payment
+/// method's currency, fees included.
+///
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BuyAmount {
+    Amount(BigDecimal),
+    Total(BigDecimal),
+}
+
+#[derive(Serialize, Debug)]
+struct BuyOrder<'a> {
+    #[serde(flatten)]
+    amount: BuyAmount,
+    currency: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payment_method: Option<&'a str>,
+    commit: bool,
+    quote: bool,
+}
+
+///
+/// Parameters for `Private::request_money`.
+///
+#[derive(Serialize, Clone, Debug)]
+pub struct MoneyRequest {
+    to: String,
+    amount: BigDecimal,
+    currency: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+impl MoneyRequest {
+    /// Request `amount` of `currency` from the owner of the email address `to`.
+    pub fn new(to: &str, amount: BigDecimal, currency: &str) -> Self {
+        Self {
+            to: to.to_string(),
+            amount,
+            currency: currency.to_string(),
+            description: None,
+        }
+    }
+
+    /// A note included in the email sent to the recipient.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct TransferParams<'a> {
+    to: &'a AccountId,
+    amount: &'a BigDecimal,
+    currency: &'a str,
+    idem: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+struct TransactionCreate<'a, P> {
+    r#type: &'static str,
+    #[serde(flatten)]
+    params: &'a P,
+}
+
+#[derive(Serialize, Debug)]
+struct AddressCreate<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+struct AccountUpdate<'a> {
+    name: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+struct UserUpdate<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_zone: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    native_currency: Option<&'a str>,
 }
 
 #[test]
@@ -213,6 +1420,26 @@ fn test_pagination_deserialize() {
     assert_eq!(Order::Descending, pagination.order);
 }
 
+#[test]
+fn test_account_id() {
+    let ids: Vec<AccountId> =
+        serde_json::from_str(r#"["f1bb8f61-7f5d-4f04-9552-bcbafdf856b7", "LINK", "ETH2"]"#)
+            .unwrap();
+    assert_eq!(
+        ids[0],
+        AccountId::Uuid(Uuid::parse_str("f1bb8f61-7f5d-4f04-9552-bcbafdf856b7").unwrap())
+    );
+    assert_eq!(ids[1], AccountId::Name("LINK".to_string()));
+    assert_eq!(ids[2], "ETH2".parse().unwrap());
+
+    assert_eq!(ids[0].to_string(), "f1bb8f61-7f5d-4f04-9552-bcbafdf856b7");
+    assert_eq!(ids[1].to_string(), "LINK");
+    assert_eq!(
+        serde_json::to_string(&ids).unwrap(),
+        r#"["f1bb8f61-7f5d-4f04-9552-bcbafdf856b7","LINK","ETH2"]"#
+    );
+}
+
 #[test]
 fn test_account_deserialize() {
     let input = r##"[
@@ -317,3 +1544,931 @@ fn test_transactions_deserialize() {
     let transactions: Vec<Transaction> = serde_json::from_slice(input.as_bytes()).unwrap();
     assert_eq!(transactions.len(), 2);
 }
+
+#[cfg(test)]
+const TWO_FACTOR_REQUIRED: &str = r#"{"errors": [{"id": "two_factor_required", "message": "Two-step verification code required to complete this request. Re-send the request with the `CB-2FA-TOKEN` header."}]}"#;
+
+/// A client sending every request through `transport`.
+#[cfg(test)]
+fn client(transport: &Arc<crate::transport::mock::MockTransport>) -> Private {
+    Private::with_transport(
+        "https://api.coinbase.com",
+        "key",
+        "secret",
+        transport.clone(),
+    )
+}
+
+/// Wrap a resource the way the API returns it.
+#[cfg(test)]
+fn data(resource: serde_json::Value) -> String {
+    serde_json::json!({ "data": resource }).to_string()
+}
+
+/// A minimal transaction, for tests to extend with the fields they check.
+#[cfg(test)]
+fn transaction_json(r#type: &str, status: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": "3c04e35e-8e5a-5ff1-9155-00675db4ac02",
+        "type": r#type,
+        "status": status,
+        "amount": {"amount": "-0.10000000", "currency": "BTC"},
+        "native_amount": {"amount": "-1.00", "currency": "USD"},
+        "resource": "transaction",
+        "resource_path": "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/transactions/3c04e35e-8e5a-5ff1-9155-00675db4ac02",
+        "instant_exchange": false,
+        "details": {"title": "Sent bitcoin", "subtitle": "to User 2"},
+    })
+}
+
+/// A wallet holding `balance` of `currency`.
+#[cfg(test)]
+fn account_json(id: &str, currency: &str, balance: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "type": "wallet",
+        "resource": "account",
+        "resource_path": format!("/v2/accounts/{}", id),
+        "name": "Wallet",
+        "primary": false,
+        "allow_deposits": true,
+        "allow_withdrawals": true,
+        "currency": {"code": currency, "name": "", "color": "", "sort_index": 100, "exponent": 8, "type": "crypto"},
+        "balance": {"amount": balance, "currency": currency},
+    })
+}
+
+/// An uncommitted buy of 0.01 BTC for 10.25 USD.
+#[cfg(test)]
+fn buy_json() -> serde_json::Value {
+    serde_json::json!({
+        "id": "a333743d-184a-5b5b-abe8-11612fc44ab5",
+        "status": "created",
+        "amount": {"amount": "0.01000000", "currency": "BTC"},
+        "total": {"amount": "10.25", "currency": "USD"},
+        "subtotal": {"amount": "10.10", "currency": "USD"},
+        "fee": {"amount": "0.15", "currency": "USD"},
+        "resource": "buy",
+        "resource_path": "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/buys/a333743d-184a-5b5b-abe8-11612fc44ab5",
+        "committed": false,
+        "instant": false,
+        "payout_at": "2015-04-01T23:43:59-07:00",
+    })
+}
+
+#[tokio::test]
+async fn test_list_addresses_sends_options() {
+    use crate::transport::mock::MockTransport;
+    use futures::TryStreamExt;
+
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::OK, r#"{"pagination": null, "data": []}"#),
+    );
+    let client = client(&transport);
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+    let options = ListOptions::new()
+        .limit(25)
+        .order(Order::Ascending)
+        .starting_after("dd3183eb-af1d-5f5d-a90d-cbff946435ff");
+
+    let pages: Vec<Page<Address>> = client
+        .list_addresses(&account, &options)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(
+        transport.uris(),
+        vec!["https://api.coinbase.com/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/addresses?limit=25&order=asc&starting_after=dd3183eb-af1d-5f5d-a90d-cbff946435ff"]
+    );
+}
+
+#[tokio::test]
+async fn test_all_transactions_reports_account_errors() {
+    use crate::transport::mock::MockTransport;
+    use http::StatusCode;
+
+    let accounts = data(serde_json::json!([
+        account_json("f1bb8f61-7f5d-4f04-9552-bcbafdf856b7", "BTC", "0.1"),
+        account_json("LINK", "LINK", "0.1"),
+    ]));
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(StatusCode::OK, &accounts)
+            .respond(
+                StatusCode::NOT_FOUND,
+                r#"{"errors": [{"id": "not_found", "message": "Not found"}]}"#,
+            )
+            .respond(
+                StatusCode::OK,
+                &data(serde_json::json!([transaction_json("send", "completed")])),
+            ),
+    );
+    let client = client(&transport);
+
+    let results: Vec<_> = client
+        .all_transactions(&ListOptions::new(), 1)
+        .collect()
+        .await;
+    assert_eq!(results.len(), 2);
+    let failed = results[0].as_ref().unwrap_err();
+    assert_eq!(
+        failed.account.as_ref().unwrap().id,
+        AccountId::Uuid(Uuid::parse_str("f1bb8f61-7f5d-4f04-9552-bcbafdf856b7").unwrap())
+    );
+    assert_eq!(failed.error.status(), Some(StatusCode::NOT_FOUND));
+    let (account, transaction) = results[1].as_ref().unwrap();
+    assert_eq!(account.id, AccountId::Name("LINK".to_string()));
+    assert_eq!(transaction.details.title, "Sent bitcoin");
+    assert_eq!(
+        transport.uris()[2],
+        "https://api.coinbase.com/v2/accounts/LINK/transactions?limit=100"
+    );
+}
+
+#[test]
+fn test_user_deserialize() {
+    let input = r#"{
+  "id": "9da7a204-544e-5fd1-9a12-61176c5d4cd8",
+  "name": "User One",
+  "username": "user1",
+  "profile_location": null,
+  "profile_bio": null,
+  "profile_url": "https://coinbase.com/user1",
+  "avatar_url": "https://images.coinbase.com/avatar?h=vR%2FY8igBoPwuwGren5JMwvDNGpURAY%2F0nRIOgH%2FY2Qh%2BQ6nomR3qusA%2Bh6o2%0Af9rH&s=128",
+  "resource": "user",
+  "resource_path": "/v2/user",
+  "email": "user1@example.com",
+  "time_zone": "Pacific Time (US & Canada)",
+  "native_currency": "USD",
+  "bitcoin_unit": "bits",
+  "state": "CA",
+  "country": {
+    "code": "US",
+    "name": "United States"
+  },
+  "created_at": "2015-01-31T20:49:02Z"
+}"#;
+    let user: User = serde_json::from_slice(input.as_bytes()).unwrap();
+    assert_eq!(user.native_currency.as_deref(), Some("USD"));
+    assert_eq!(user.country.unwrap().code, "US");
+
+    let input = r#"{
+  "id": "9da7a204-544e-5fd1-9a12-61176c5d4cd8",
+  "name": "User One",
+  "username": "user1",
+  "profile_location": null,
+  "profile_bio": null,
+  "profile_url": "https://coinbase.com/user1",
+  "avatar_url": "https://images.coinbase.com/avatar?h=vR%2FY8igBoPwuwGren5JMwvDNGpURAY%2F0nRIOgH%2FY2Qh%2BQ6nomR3qusA%2Bh6o2%0Af9rH&s=128",
+  "resource": "user",
+  "resource_path": "/v2/users/9da7a204-544e-5fd1-9a12-61176c5d4cd8"
+}"#;
+    let user: User = serde_json::from_slice(input.as_bytes()).unwrap();
+    assert!(user.email.is_none());
+}
+
+#[test]
+fn test_auth_info_deserialize() {
+    let input = r#"{
+  "method": "oauth",
+  "scopes": [
+    "wallet:user:read",
+    "wallet:user:email"
+  ],
+  "oauth_meta": {
+    "send_limit_amount": "1.00",
+    "send_limit_currency": "USD",
+    "send_limit_period": "day"
+  }
+}"#;
+    let auth: AuthInfo = serde_json::from_slice(input.as_bytes()).unwrap();
+    assert!(auth.has_scope("wallet:user:email"));
+    assert!(!auth.has_scope("wallet:transactions:send"));
+    assert_eq!(
+        auth.oauth_meta.unwrap().send_limit_currency.as_deref(),
+        Some("USD")
+    );
+
+    let auth: AuthInfo =
+        serde_json::from_str(r#"{"method": "api_key", "scopes": ["wallet:accounts:read"]}"#)
+            .unwrap();
+    assert!(auth.oauth_meta.is_none());
+}
+
+#[tokio::test]
+async fn test_update_current_user_sends_signed_put() {
+    use crate::transport::mock::MockTransport;
+
+    let transport = Arc::new(MockTransport::default().respond(
+        http::StatusCode::OK,
+        r#"{"data": {"id": "9da7a204-544e-5fd1-9a12-61176c5d4cd8", "name": "James Smith",
+            "resource": "user", "resource_path": "/v2/user", "native_currency": "EUR"}}"#,
+    ));
+    let client = client(&transport);
+
+    let user = client
+        .update_current_user(Some("James Smith"), None, Some("EUR"))
+        .await
+        .unwrap();
+    assert_eq!(user.native_currency.as_deref(), Some("EUR"));
+
+    let requests = transport.requests.lock().unwrap();
+    let request = &requests[0];
+    assert_eq!(request.method(), Method::PUT);
+    assert_eq!(request.uri(), "https://api.coinbase.com/v2/user");
+    assert!(request.headers().contains_key("CB-ACCESS-SIGN"));
+    assert_eq!(
+        request.body().as_slice(),
+        br#"{"name":"James Smith","native_currency":"EUR"}"#
+    );
+}
+
+#[tokio::test]
+async fn test_account_management_requests() {
+    use crate::transport::mock::MockTransport;
+
+    let account = r##"{"data": {"id": "LINK", "type": "wallet", "resource": "account", "resource_path": "/v2/accounts/LINK",
+        "name": "Chainlink", "primary": true, "allow_deposits": true, "allow_withdrawals": true,
+        "currency": {"code": "LINK", "name": "Chainlink", "color": "#2A5ADA", "sort_index": 100, "exponent": 8, "type": "crypto"},
+        "balance": {"amount": "0.00000000", "currency": "LINK"}}}"##;
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::OK, account)
+            .respond(http::StatusCode::OK, account)
+            .respond(http::StatusCode::NO_CONTENT, ""),
+    );
+    let client = client(&transport);
+    let id = AccountId::from("LINK");
+
+    let account = client.update_account(&id, "Chainlink").await.unwrap();
+    assert_eq!(account.name, "Chainlink");
+    let account = client.set_primary_account(&id).await.unwrap();
+    assert!(account.primary);
+    client.delete_account(&id).await.unwrap();
+
+    let requests = transport.requests.lock().unwrap();
+    let summary: Vec<_> = requests
+        .iter()
+        .map(|request| {
+            (
+                request.method().clone(),
+                request.uri().path().to_string(),
+                String::from_utf8(request.body().clone()).unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                Method::PUT,
+                "/v2/accounts/LINK".to_string(),
+                r#"{"name":"Chainlink"}"#.to_string()
+            ),
+            (
+                Method::POST,
+                "/v2/accounts/LINK/primary".to_string(),
+                String::new()
+            ),
+            (
+                Method::DELETE,
+                "/v2/accounts/LINK".to_string(),
+                String::new()
+            ),
+        ]
+    );
+    assert!(requests
+        .iter()
+        .all(|request| request.headers().contains_key("CB-ACCESS-SIGN")));
+}
+
+#[test]
+fn test_address_deserialize() {
+    let input = r#"{
+  "id": "dd3183eb-af1d-5f5d-a90d-cbff946435ff",
+  "address": "mswUGcPHp1YnkLCgF1TtoryqSc5E9Q8xFa",
+  "name": "One off payment",
+  "created_at": "2015-01-31T20:49:02Z",
+  "updated_at": "2015-03-31T17:25:29-07:00",
+  "network": "bitcoin",
+  "resource": "address",
+  "resource_path": "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/addresses/dd3183eb-af1d-5f5d-a90d-cbff946435ff",
+  "uri_scheme": "bitcoin",
+  "deposit_uri": "bitcoin:mswUGcPHp1YnkLCgF1TtoryqSc5E9Q8xFa",
+  "address_info": {
+    "address": "mswUGcPHp1YnkLCgF1TtoryqSc5E9Q8xFa"
+  },
+  "callback_url": null,
+  "exchange_deposit_address": false,
+  "warnings": [
+    {
+      "title": "Only send Bitcoin (BTC) to this address",
+      "details": "Sending any other digital asset, including Bitcoin Cash (BCH), will result in permanent loss.",
+      "image_url": "https://dynamic-assets.coinbase.com/e785e0181f1a23a30d9476038d9be91e9f6c63959b538eabbc51a1abc8898940383291eede695c3b8dfaa1829a9b57f5a2d0a16b0523580346c6b8fab67af14b/asset_icons/b57ac673f06a4b0338a596817eb0a50ce16e2059f327dc117744449a47915cb2.png"
+    }
+  ]
+}"#;
+    let address: Address = serde_json::from_slice(input.as_bytes()).unwrap();
+    assert_eq!(address.uri_scheme.as_deref(), Some("bitcoin"));
+    assert_eq!(
+        address.deposit_uri.as_deref(),
+        Some("bitcoin:mswUGcPHp1YnkLCgF1TtoryqSc5E9Q8xFa")
+    );
+    assert_eq!(address.warnings.len(), 1);
+}
+
+#[tokio::test]
+async fn test_address_requests() {
+    use crate::transport::mock::MockTransport;
+    use futures::TryStreamExt;
+
+    let address = r#"{"data": {"id": "dd3183eb-af1d-5f5d-a90d-cbff946435ff", "address": "mswUGcPHp1YnkLCgF1TtoryqSc5E9Q8xFa",
+        "name": "Customer 42", "network": "bitcoin", "resource": "address",
+        "resource_path": "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/addresses/dd3183eb-af1d-5f5d-a90d-cbff946435ff"}}"#;
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::CREATED, address)
+            .respond(http::StatusCode::OK, r#"{"data": []}"#),
+    );
+    let client = client(&transport);
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+
+    let address = client
+        .create_address(&account, Some("Customer 42"))
+        .await
+        .unwrap();
+    assert_eq!(address.name.as_deref(), Some("Customer 42"));
+
+    let pages: Vec<Page<Transaction>> = client
+        .address_transactions(&account, &address.id, &ListOptions::new())
+        .try_collect()
+        .await
+        .unwrap();
+    assert!(pages[0].data.is_empty());
+
+    assert_eq!(
+        transport.uris(),
+        vec![
+            "https://api.coinbase.com/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/addresses",
+            "https://api.coinbase.com/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/addresses/dd3183eb-af1d-5f5d-a90d-cbff946435ff/transactions?limit=100",
+        ]
+    );
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests[0].method(), Method::POST);
+    assert_eq!(requests[0].body().as_slice(), br#"{"name":"Customer 42"}"#);
+}
+
+#[tokio::test]
+async fn test_address_lookup() {
+    use crate::transport::mock::MockTransport;
+
+    let address = r#"{"data": {"id": "dd3183eb-af1d-5f5d-a90d-cbff946435ff", "address": "mswUGcPHp1YnkLCgF1TtoryqSc5E9Q8xFa",
+        "name": "Customer 42", "network": "bitcoin", "resource": "address",
+        "resource_path": "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/addresses/dd3183eb-af1d-5f5d-a90d-cbff946435ff"}}"#;
+    let transport = Arc::new(MockTransport::default().respond(http::StatusCode::OK, address));
+    let client = client(&transport);
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+
+    let address = client
+        .address(&account, "mswUGcPHp1YnkLCgF1TtoryqSc5E9Q8xFa")
+        .await
+        .unwrap();
+    assert_eq!(address.id, "dd3183eb-af1d-5f5d-a90d-cbff946435ff");
+    assert_eq!(address.name.as_deref(), Some("Customer 42"));
+    assert_eq!(
+        transport.uris(),
+        vec!["https://api.coinbase.com/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/addresses/mswUGcPHp1YnkLCgF1TtoryqSc5E9Q8xFa"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_wait_for_transaction() {
+    use crate::transport::mock::MockTransport;
+
+    let transaction = |status| data(transaction_json("send", status));
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::OK, &transaction("pending"))
+            .respond(http::StatusCode::OK, &transaction("pending"))
+            .respond(http::StatusCode::OK, &transaction("completed"))
+            .respond(http::StatusCode::OK, &transaction("pending"))
+            .respond(http::StatusCode::OK, &transaction("pending")),
+    );
+    let client = client(&transport);
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+    let id = Uuid::parse_str("3c04e35e-8e5a-5ff1-9155-00675db4ac02").unwrap();
+
+    let completed = client
+        .wait_for_transaction(
+            &account,
+            &id,
+            Duration::from_secs(5),
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+    assert_eq!(completed.status, "completed");
+    assert_eq!(transport.uris().len(), 3);
+    assert_eq!(
+        transport.uris()[0],
+        "https://api.coinbase.com/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/transactions/3c04e35e-8e5a-5ff1-9155-00675db4ac02"
+    );
+
+    let timeout = client
+        .wait_for_transaction(
+            &account,
+            &id,
+            Duration::from_secs(5),
+            Duration::from_secs(8),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(timeout, CBError::Timeout(_)));
+    assert_eq!(transport.uris().len(), 5);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_send_money_retries_with_same_idem() {
+    use crate::transport::mock::MockTransport;
+
+    let transaction = data(transaction_json("send", "pending"));
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::SERVICE_UNAVAILABLE, "")
+            .respond(http::StatusCode::CREATED, &transaction),
+    );
+    let client = client(&transport);
+    let send = SendRequest::new(
+        "rLHzPsX6oXkzU2qL12kHCH8G8cnZv1rBJh",
+        "0.1".parse().unwrap(),
+        "XRP",
+    )
+    .destination_tag("12345")
+    .skip_notifications(true);
+    assert_eq!(send.idem_token().len(), 36);
+    assert_ne!(
+        send.idem_token(),
+        SendRequest::new("a", BigDecimal::from(1), "BTC").idem_token()
+    );
+
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+    let transaction = client.send_money(&account, &send).await.unwrap();
+    assert!(transaction.is_pending());
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].body(), requests[1].body());
+    let body: serde_json::Value = serde_json::from_slice(requests[0].body()).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "type": "send",
+            "to": "rLHzPsX6oXkzU2qL12kHCH8G8cnZv1rBJh",
+            "amount": "0.1",
+            "currency": "XRP",
+            "skip_notifications": true,
+            "idem": send.idem_token(),
+            "destination_tag": "12345",
+        })
+    );
+    assert_eq!(
+        requests[0].uri().path(),
+        "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/transactions"
+    );
+}
+
+#[tokio::test]
+async fn test_send_money_with_two_factor() {
+    use crate::transport::mock::MockTransport;
+
+    let transaction = data(transaction_json("send", "pending"));
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::PAYMENT_REQUIRED, TWO_FACTOR_REQUIRED)
+            .respond(http::StatusCode::CREATED, &transaction)
+            .respond(http::StatusCode::PAYMENT_REQUIRED, TWO_FACTOR_REQUIRED)
+            .respond(http::StatusCode::CREATED, &transaction),
+    );
+    let client = client(&transport);
+    let account = AccountId::from("BTC");
+    let send = SendRequest::new("user2@example.com", "0.1".parse().unwrap(), "BTC");
+
+    let transaction = client
+        .send_money_with_two_factor(&account, &send, || async { Some("123456".to_string()) })
+        .await
+        .unwrap();
+    assert!(transaction.is_pending());
+
+    let error = client
+        .send_money_with_two_factor(&account, &send, || async { None })
+        .await
+        .unwrap_err();
+    assert!(error.is_two_factor_required());
+
+    client
+        .with_two_factor_token("654321")
+        .send_money(&account, &send)
+        .await
+        .unwrap();
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests.len(), 4);
+    assert!(!requests[0].headers().contains_key("CB-2FA-TOKEN"));
+    assert_eq!(requests[1].headers()["CB-2FA-TOKEN"], "123456");
+    assert!(requests[1].headers().contains_key("CB-ACCESS-SIGN"));
+    assert_eq!(requests[0].body(), requests[1].body());
+    assert!(!requests[2].headers().contains_key("CB-2FA-TOKEN"));
+    assert_eq!(requests[3].headers()["CB-2FA-TOKEN"], "654321");
+}
+
+#[tokio::test]
+async fn test_call_with_two_factor() {
+    use crate::transport::mock::MockTransport;
+
+    let mut committed = buy_json();
+    committed["committed"] = true.into();
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::PAYMENT_REQUIRED, TWO_FACTOR_REQUIRED)
+            .respond(http::StatusCode::OK, &data(committed)),
+    );
+    let client = client(&transport);
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+    let id = Uuid::parse_str("a333743d-184a-5b5b-abe8-11612fc44ab5").unwrap();
+
+    let buy = client
+        .call_with_two_factor(
+            |client| {
+                let (account, id) = (&account, &id);
+                async move { client.commit_buy(account, id).await }
+            },
+            || async { Some("123456".to_string()) },
+        )
+        .await
+        .unwrap();
+    assert!(buy.committed);
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(!requests[0].headers().contains_key("CB-2FA-TOKEN"));
+    assert_eq!(requests[1].headers()["CB-2FA-TOKEN"], "123456");
+    assert_eq!(requests[0].uri(), requests[1].uri());
+}
+
+#[tokio::test]
+async fn test_transfer_money_validates_accounts() {
+    use crate::transport::mock::MockTransport;
+
+    let account = |id, currency, balance| data(account_json(id, currency, balance));
+    let vault = "58542935-67b5-56e1-a3f9-42686e07fa40";
+    let primary = "2bbf394c-193b-5b2a-9155-3b4732659ede";
+    let transaction = data(transaction_json("transfer", "completed"));
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::OK, &account(vault, "BTC", "2.0"))
+            .respond(http::StatusCode::OK, &account(primary, "ETH", "0.0"))
+            .respond(http::StatusCode::OK, &account(vault, "BTC", "0.5"))
+            .respond(http::StatusCode::OK, &account(primary, "BTC", "0.0"))
+            .respond(http::StatusCode::OK, &account(vault, "BTC", "2.0"))
+            .respond(http::StatusCode::OK, &account(vault, "BTC", "2.0"))
+            .respond(http::StatusCode::OK, &account(vault, "BTC", "2.0"))
+            .respond(http::StatusCode::OK, &account(primary, "BTC", "0.0"))
+            .respond(http::StatusCode::CREATED, &transaction),
+    );
+    let client = client(&transport);
+    let (from, to) = (AccountId::from(vault), AccountId::from(primary));
+    let amount = BigDecimal::from(1);
+
+    let error = client
+        .transfer_money(&from, &to, &amount, "BTC", None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        CBError::Validation(ValidationError::CurrencyMismatch { ref account_currency, .. })
+            if account_currency == "ETH"
+    ));
+
+    let error = client
+        .transfer_money(&from, &to, &amount, "BTC", None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        CBError::Validation(ValidationError::InsufficientFunds { .. })
+    ));
+
+    let error = client
+        .transfer_money(&from, &from, &amount, "BTC", None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        CBError::Validation(ValidationError::SameAccount { ref account }) if account == vault
+    ));
+
+    let transaction = client
+        .transfer_money(&from, &to, &amount, "btc", Some("sweep-2026-10-18"))
+        .await
+        .unwrap();
+    assert_eq!(transaction.r#type, "transfer");
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests.len(), 9);
+    let body: serde_json::Value = serde_json::from_slice(requests[8].body()).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "type": "transfer",
+            "to": primary,
+            "amount": "1",
+            "currency": "BTC",
+            "idem": "sweep-2026-10-18",
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_money_request_workflow() {
+    use crate::transport::mock::MockTransport;
+
+    let mut transaction = transaction_json("request", "pending");
+    transaction["expires_at"] = "2015-05-01T10:37:11-07:00".into();
+    transaction["to"] = serde_json::json!({"resource": "email", "email": "email@example.com"});
+    let transaction = data(transaction);
+    let completed = data(transaction_json("request", "completed"));
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::CREATED, &transaction)
+            .respond(http::StatusCode::OK, "")
+            .respond(http::StatusCode::PAYMENT_REQUIRED, TWO_FACTOR_REQUIRED)
+            .respond(http::StatusCode::OK, &completed)
+            .respond(http::StatusCode::NO_CONTENT, ""),
+    );
+    let client = client(&transport);
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+
+    let request = MoneyRequest::new("email@example.com", "1".parse().unwrap(), "BTC")
+        .description("Invoice 1234");
+    let transaction = client.request_money(&account, &request).await.unwrap();
+    assert_eq!(
+        transaction.to.as_ref().unwrap().email.as_deref(),
+        Some("email@example.com")
+    );
+    assert!(transaction.expires_at.is_some());
+
+    client
+        .resend_money_request(&account, &transaction.id)
+        .await
+        .unwrap();
+    let completed = client
+        .call_with_two_factor(
+            |client| {
+                let (account, id) = (&account, &transaction.id);
+                async move { client.complete_money_request(account, id).await }
+            },
+            || async { Some("123456".to_string()) },
+        )
+        .await
+        .unwrap();
+    assert_eq!(completed.status, "completed");
+    client
+        .cancel_money_request(&account, &transaction.id)
+        .await
+        .unwrap();
+
+    let requests = transport.requests.lock().unwrap();
+    let body: serde_json::Value = serde_json::from_slice(requests[0].body()).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "type": "request",
+            "to": "email@example.com",
+            "amount": "1",
+            "currency": "BTC",
+            "description": "Invoice 1234",
+        })
+    );
+    let calls: Vec<_> = requests[1..]
+        .iter()
+        .map(|request| (request.method().clone(), request.uri().path().to_string()))
+        .collect();
+    let path = "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/transactions/3c04e35e-8e5a-5ff1-9155-00675db4ac02";
+    assert_eq!(
+        calls,
+        vec![
+            (Method::POST, format!("{}/resend", path)),
+            (Method::POST, format!("{}/complete", path)),
+            (Method::POST, format!("{}/complete", path)),
+            (Method::DELETE, path.to_string()),
+        ]
+    );
+    assert_eq!(requests[3].headers()["CB-2FA-TOKEN"], "123456");
+}
+
+#[test]
+fn test_buy_deserialize() {
+    let input = r#"{
+  "id": "67e0eaec-07d7-54c4-a72c-2e92826897df",
+  "status": "completed",
+  "payment_method": {
+    "id": "83562370-3e5c-51db-87da-752af5ab9559",
+    "resource": "payment_method",
+    "resource_path": "/v2/payment-methods/83562370-3e5c-51db-87da-752af5ab9559"
+  },
+  "transaction": {
+    "id": "441b9494-b3f0-5b98-b9b0-4d82c21c252a",
+    "resource": "transaction",
+    "resource_path": "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/transactions/441b9494-b3f0-5b98-b9b0-4d82c21c252a"
+  },
+  "amount": {
+    "amount": "10.00000000",
+    "currency": "BTC"
+  },
+  "total": {
+    "amount": "102.01",
+    "currency": "USD"
+  },
+  "subtotal": {
+    "amount": "101.00",
+    "currency": "USD"
+  },
+  "unit_price": {
+    "amount": "10.10",
+    "currency": "USD",
+    "scale": 2
+  },
+  "created_at": "2015-01-31T20:49:02Z",
+  "updated_at": "2015-02-11T16:54:02-08:00",
+  "resource": "buy",
+  "resource_path": "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/buys/67e0eaec-07d7-54c4-a72c-2e92826897df",
+  "committed": true,
+  "instant": false,
+  "fee": {
+    "amount": "1.01",
+    "currency": "USD"
+  },
+  "payout_at": "2015-02-18T16:54:00-08:00"
+}"#;
+    let buy: Buy = serde_json::from_slice(input.as_bytes()).unwrap();
+    assert!(buy.committed);
+    assert_eq!(buy.fee.amount, "1.01".parse().unwrap());
+    assert_eq!(buy.unit_price.unwrap().amount, "10.10".parse().unwrap());
+    assert!(buy.payout_at.is_some());
+}
+
+#[tokio::test]
+async fn test_buys_list_and_show() {
+    use crate::transport::mock::MockTransport;
+    use futures::TryStreamExt;
+
+    let path = "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/buys";
+    let mut older = buy_json();
+    older["id"] = "67e0eaec-07d7-54c4-a72c-2e92826897df".into();
+    older["committed"] = true.into();
+    older["status"] = "completed".into();
+    let first = serde_json::json!({
+        "pagination": {
+            "limit": 1,
+            "order": "desc",
+            "next_starting_after": "a333743d-184a-5b5b-abe8-11612fc44ab5",
+            "next_uri": format!("{}?limit=1&starting_after=a333743d-184a-5b5b-abe8-11612fc44ab5", path),
+        },
+        "data": [buy_json()],
+    });
+    let second = serde_json::json!({
+        "pagination": {"limit": 1, "order": "desc", "next_uri": null},
+        "data": [older.clone()],
+    });
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::OK, &first.to_string())
+            .respond(http::StatusCode::OK, &second.to_string())
+            .respond(http::StatusCode::OK, &data(older)),
+    );
+    let client = client(&transport);
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+
+    let pages: Vec<Page<Buy>> = client
+        .buys(&account, &ListOptions::new().limit(1))
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(pages.len(), 2);
+    assert_eq!(
+        pages[0].next_cursor(),
+        Some("a333743d-184a-5b5b-abe8-11612fc44ab5")
+    );
+    assert!(!pages[0].data[0].committed);
+    assert_eq!(pages[1].data[0].status, "completed");
+
+    let id = Uuid::parse_str("67e0eaec-07d7-54c4-a72c-2e92826897df").unwrap();
+    let buy = client.buy(&account, &id).await.unwrap();
+    assert_eq!(buy.id, id);
+    assert!(buy.committed);
+    assert_eq!(buy.total.amount, "10.25".parse().unwrap());
+
+    assert_eq!(
+        transport.uris(),
+        vec![
+            format!("https://api.coinbase.com{}?limit=1", path),
+            format!(
+                "https://api.coinbase.com{}?limit=1&starting_after=a333743d-184a-5b5b-abe8-11612fc44ab5",
+                path
+            ),
+            format!("https://api.coinbase.com{}/67e0eaec-07d7-54c4-a72c-2e92826897df", path),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_buy_quote() {
+    use crate::transport::mock::MockTransport;
+
+    let transport =
+        Arc::new(MockTransport::default().respond(http::StatusCode::CREATED, &data(buy_json())));
+    let client = client(&transport);
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+
+    let quote = client
+        .place_buy_order(
+            &account,
+            BuyAmount::Total("10.25".parse().unwrap()),
+            "USD",
+            Some("83562370-3e5c-51db-87da-752af5ab9559"),
+            false,
+            true,
+        )
+        .await
+        .unwrap();
+    assert!(!quote.committed);
+    assert_eq!(quote.fee.amount, "0.15".parse().unwrap());
+    assert_eq!(quote.subtotal.amount, "10.10".parse().unwrap());
+    assert_eq!(quote.total.amount, "10.25".parse().unwrap());
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    let body: serde_json::Value = serde_json::from_slice(requests[0].body()).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "total": "10.25",
+            "currency": "USD",
+            "payment_method": "83562370-3e5c-51db-87da-752af5ab9559",
+            "commit": false,
+            "quote": true,
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_buy_place_and_commit() {
+    use crate::transport::mock::MockTransport;
+
+    let mut committed = buy_json();
+    committed["committed"] = true.into();
+    committed["status"] = "completed".into();
+    let transport = Arc::new(
+        MockTransport::default()
+            .respond(http::StatusCode::CREATED, &data(buy_json()))
+            .respond(http::StatusCode::OK, &data(committed)),
+    );
+    let client = client(&transport);
+    let account = AccountId::from("2bbf394c-193b-5b2a-9155-3b4732659ede");
+
+    let pending = client
+        .place_buy_order(
+            &account,
+            BuyAmount::Amount("0.01".parse().unwrap()),
+            "BTC",
+            None,
+            false,
+            false,
+        )
+        .await
+        .unwrap();
+    assert!(!pending.committed);
+
+    let buy = client.commit_buy(&account, &pending.id).await.unwrap();
+    assert!(buy.committed);
+    assert_eq!(buy.status, "completed");
+
+    let requests = transport.requests.lock().unwrap();
+    let body: serde_json::Value = serde_json::from_slice(requests[0].body()).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "amount": "0.01",
+            "currency": "BTC",
+            "commit": false,
+            "quote": false,
+        })
+    );
+    assert_eq!(requests[1].method(), Method::POST);
+    assert_eq!(
+        requests[1].uri().path(),
+        "/v2/accounts/2bbf394c-193b-5b2a-9155-3b4732659ede/buys/a333743d-184a-5b5b-abe8-11612fc44ab5/commit"
+    );
+}
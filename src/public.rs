@@ -1,30 +1,108 @@
 use std::collections::HashMap;
-use std::thread;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::Instant;
 
 use async_stream::try_stream;
 use bigdecimal::BigDecimal;
 use futures::stream::Stream;
-use hyper::{client::HttpConnector, Body, Client, Uri};
-use hyper_tls::HttpsConnector;
 use uritemplate::UriTemplate;
 
+use crate::builder::{ClientBuilder, Defaults};
+use crate::error::{self, Warning};
+use crate::metrics::{Metrics, RateLimitStatus, RequestMetrics};
+use crate::pagination::{Direction, ListOptions, Page};
+use crate::rate_limit::RateLimiter;
+use crate::retry::{self, RetryPolicy};
+use crate::trace;
+use crate::transport::{HyperTransport, Transport};
 use crate::{request, CBError, DateTime, Result};
 
+pub use crate::pagination::{Order, Pagination};
+
+#[derive(Clone)]
 pub struct Public {
     pub(crate) uri: String,
-    client: Client<HttpsConnector<HttpConnector>>,
+    transport: Arc<dyn Transport>,
+    rate_limiter: Arc<RateLimiter>,
+    retry_policy: RetryPolicy,
+    defaults: Defaults,
+    metrics: Option<Arc<dyn Metrics>>,
 }
 
 impl Public {
     pub fn new(uri: &str) -> Self {
-        let https = HttpsConnector::new();
-        let client = Client::builder()
-            .pool_idle_timeout(Duration::from_secs(30))
-            .build::<_, Body>(https);
-        let uri = uri.to_string();
+        Self::with_transport(uri, HyperTransport::new())
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    pub(crate) fn from_parts(
+        uri: String,
+        transport: Arc<dyn Transport>,
+        rate_limiter: Arc<RateLimiter>,
+        retry_policy: RetryPolicy,
+        defaults: Defaults,
+        metrics: Option<Arc<dyn Metrics>>,
+    ) -> Self {
+        Self {
+            uri,
+            transport,
+            rate_limiter,
+            retry_policy,
+            defaults,
+            metrics,
+        }
+    }
+
+    ///
+    /// Create a client that sends its requests through `transport` instead of the default hyper
+    /// client.
+    ///
+    pub fn with_transport<T>(uri: &str, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self::from_parts(
+            uri.to_string(),
+            Arc::new(transport),
+            Arc::new(RateLimiter::default()),
+            RetryPolicy::default(),
+            Defaults::default(),
+            None,
+        )
+    }
+
+    ///
+    /// Replace the default rate limiter, e.g. to share one budget between several clients.
+    ///
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    pub fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    ///
+    /// Report the outcome of every call to `metrics`.
+    ///
+    pub fn with_metrics<M>(mut self, metrics: M) -> Self
+    where
+        M: Metrics + 'static,
+    {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
 
-        Self { uri, client }
+    ///
+    /// Replace the default policy for retrying connection errors, 429 and 5xx responses.
+    ///
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 
     ///
@@ -36,13 +114,14 @@ impl Public {
     ///
     /// https://developers.coinbase.com/api/v2#currencies
     ///
-    pub fn currencies<'a>(&'a self) -> impl Stream<Item = Result<Vec<Currency>>> + 'a {
-        let limit = 100;
+    pub fn currencies<'a>(
+        &'a self,
+        options: &ListOptions,
+    ) -> impl Stream<Item = Result<Page<Currency>>> + 'a {
         let uri = UriTemplate::new("/v2/currencies{?query*}")
-            .set("query", &[("limit", limit.to_string().as_ref())])
+            .set("query", options.query())
             .build();
-        let request = self.request(&uri);
-        self.get_stream(request)
+        self.get_stream(self.request("/v2/currencies", &uri), options.direction)
     }
 
     ///
@@ -58,7 +137,7 @@ impl Public {
         let uri = UriTemplate::new("/v2/exchange-rates{?currency*}")
             .set("currency", currency)
             .build();
-        self.get(&uri).await
+        self.get("/v2/exchange-rates", &uri).await
     }
 
     ///
@@ -72,7 +151,7 @@ impl Public {
         let uri = UriTemplate::new("/v2/prices/{pair}")
             .set("pair", pair)
             .build();
-        self.get(&uri).await
+        self.get("/v2/prices/{pair}", &uri).await
     }
 
     ///
@@ -83,8 +162,11 @@ impl Public {
     /// https://developers.coinbase.com/api/v2#get-sell-price
     ///
     pub async fn sell_price(&self, currency_pair: &str) -> Result<CurrencyPrice> {
-        self.get(&format!("/v2/prices/{}/sell", currency_pair))
-            .await
+        self.get(
+            "/v2/prices/{pair}/sell",
+            &format!("/v2/prices/{}/sell", currency_pair),
+        )
+        .await
     }
 
     ///
@@ -100,8 +182,11 @@ impl Public {
         currency_pair: &str,
         _date: Option<chrono::NaiveDate>,
     ) -> Result<CurrencyPrice> {
-        self.get(&format!("/v2/prices/{}/spot", currency_pair))
-            .await
+        self.get(
+            "/v2/prices/{pair}/spot",
+            &format!("/v2/prices/{}/spot", currency_pair),
+        )
+        .await
     }
 
     ///
@@ -112,66 +197,190 @@ impl Public {
     /// https://developers.coinbase.com/api/v2#time
     ///
     pub async fn current_time(&self) -> Result<Time> {
-        self.get("/v2/time").await
+        self.get("/v2/time", "/v2/time").await
     }
 
     pub(crate) async fn make_request<U>(&self, request: request::Builder) -> Result<Response<U>>
     where
         U: serde::de::DeserializeOwned,
     {
-        // TODO: This is for rate limiting purposes, but this is super-hacky
-        thread::sleep(Duration::from_millis(350));
+        trace::request(&request, self.execute(&request)).await
+    }
 
-        let request = request.clone().build();
-        let request_future = self.client.request(request);
+    ///
+    /// Send a request whose response has no `data`, e.g. `204 No Content` to a `DELETE`.
+    ///
+    pub(crate) async fn make_empty_request(&self, request: request::Builder) -> Result<()> {
+        trace::request(&request, self.fetch(&request)).await?;
+        Ok(())
+    }
 
-        let response = request_future.await?;
-        let body = hyper::body::to_bytes(response.into_body()).await?;
+    async fn execute<U>(&self, request: &request::Builder) -> Result<Response<U>>
+    where
+        U: serde::de::DeserializeOwned,
+    {
+        let body = self.fetch(request).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
 
-        match serde_json::from_slice::<Response<U>>(&body) {
-            Ok(body) => Ok(body),
-            Err(e) => match serde_json::from_slice(&body) {
-                Ok(coinbase_err) => Err(CBError::Coinbase(coinbase_err)),
-                Err(_) => Err(CBError::Serde(e)),
-            },
+    /// Send `request`, retrying as configured, and return the body of a successful response.
+    async fn fetch(&self, request: &request::Builder) -> Result<hyper::body::Bytes> {
+        let start = Instant::now();
+        let mut attempt = 1;
+        let result = loop {
+            self.rate_limiter.acquire(request.is_authenticated()).await;
+
+            let built = match request.clone().build() {
+                Ok(built) => built,
+                Err(e) => {
+                    self.record(request, None, start, 0, attempt, &http::HeaderMap::new());
+                    return Err(e.into());
+                }
+            };
+            trace::attempt(&built, attempt);
+            let result = self.send(built).await;
+            let (status, retry_after) = match result {
+                Ok(ref response) if retry::is_retryable_status(response.status()) => (
+                    Some(response.status()),
+                    retry::retry_after(response.headers()),
+                ),
+                Ok(_) => break result,
+                Err(_) => (None, None),
+            };
+
+            if !self.retry_policy.should_retry(request, attempt) {
+                break result;
+            }
+            let delay = self.retry_policy.delay(attempt, retry_after);
+            trace::retry(attempt, delay, status);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        };
+
+        let (parts, body) = match result {
+            Ok(response) => {
+                let (parts, body) = response.into_parts();
+                (parts, hyper::body::to_bytes(body).await)
+            }
+            Err(e) => {
+                self.record(request, None, start, 0, attempt, &http::HeaderMap::new());
+                return Err(e);
+            }
+        };
+        let body = match body {
+            Ok(body) => body,
+            Err(e) => {
+                self.record(request, None, start, 0, attempt, &parts.headers);
+                return Err(e.into());
+            }
+        };
+        trace::response(parts.status, start.elapsed(), &body);
+        self.record(
+            request,
+            Some(parts.status),
+            start,
+            body.len(),
+            attempt,
+            &parts.headers,
+        );
+
+        if !parts.status.is_success() {
+            return Err(CBError::Coinbase(error::Error::new(
+                parts.status,
+                &parts.headers,
+                &body,
+            )));
+        }
+        Ok(body)
+    }
+
+    fn record(
+        &self,
+        request: &request::Builder,
+        status: Option<http::StatusCode>,
+        start: Instant,
+        bytes: usize,
+        attempts: u32,
+        headers: &http::HeaderMap,
+    ) {
+        if let Some(ref metrics) = self.metrics {
+            metrics.record(&RequestMetrics {
+                endpoint: request.endpoint_template(),
+                method: &request.parts().method,
+                status,
+                latency: start.elapsed(),
+                bytes,
+                attempts,
+                rate_limit: RateLimitStatus::from_headers(headers),
+            });
+        }
+    }
+
+    async fn send(
+        &self,
+        request: http::Request<hyper::Body>,
+    ) -> Result<http::Response<hyper::Body>> {
+        let response = self.transport.send(request);
+        match self.defaults.request_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, response)
+                .await
+                .map_err(|_| CBError::Timeout(timeout))?,
+            None => response.await,
         }
     }
 
     pub(crate) fn get_stream<'a, U>(
         &'a self,
-        request: request::Builder,
-    ) -> impl Stream<Item = Result<U>> + 'a
+        request: Result<request::Builder>,
+        direction: Direction,
+    ) -> impl Stream<Item = Result<Page<U>>> + 'a
     where
         U: Send + 'static,
         U: serde::de::DeserializeOwned,
         U: std::marker::Unpin,
     {
         try_stream! {
-            let initial_request = request.clone();
-            let mut result = self.make_request(initial_request).await?;
-            yield result.data;
-
-            while let Some(ref next_uri) = result.pagination.and_then(|p| p.next_uri) {
-                let uri: Uri = (self.uri.to_string() + next_uri).parse().unwrap();
-                let request = request.clone().uri(uri);
-                result = self.make_request(request).await?;
-                yield result.data;
+            let request = request?;
+            let mut current = request.clone();
+            let mut page = 1;
+            loop {
+                let response: Response<Vec<U>> =
+                    trace::page(page, self.make_request(current)).await?;
+                let uri = response
+                    .pagination
+                    .as_ref()
+                    .and_then(|pagination| pagination.uri(direction).map(str::to_string));
+                yield Page {
+                    data: response.data,
+                    pagination: response.pagination,
+                };
+
+                match uri {
+                    Some(uri) => current = request.clone().uri(request::join(&self.uri, &uri)?),
+                    None => break,
+                }
+                page += 1;
             }
         }
     }
 
-    async fn get<U>(&self, uri: &str) -> Result<U>
+    async fn get<U>(&self, endpoint: &'static str, uri: &str) -> Result<U>
     where
         U: Send + 'static,
         U: serde::de::DeserializeOwned,
     {
-        let result = self.make_request(self.request(uri)).await?;
+        let result = self.make_request(self.request(endpoint, uri)?).await?;
         Ok(result.data)
     }
 
-    fn request(&self, uri: &str) -> request::Builder {
-        let uri: Uri = (self.uri.to_string() + uri).parse().unwrap();
-        request::Builder::new().uri(uri)
+    fn request(&self, endpoint: &'static str, uri: &str) -> Result<request::Builder> {
+        let uri = request::join(&self.uri, uri)?;
+        Ok(self.prepare(request::Builder::new().uri(uri).endpoint(endpoint)))
+    }
+
+    /// Apply the client's default headers and version to a request.
+    pub(crate) fn prepare(&self, request: request::Builder) -> request::Builder {
+        self.defaults.apply(request)
     }
 }
 
@@ -185,26 +394,8 @@ pub struct Time {
 pub struct Response<U> {
     pub pagination: Option<Pagination>,
     pub data: U,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub enum Order {
-    #[serde(rename = "asc")]
-    Ascending,
-    #[serde(rename = "desc")]
-    Descending,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct Pagination {
-    pub ending_before: Option<String>,
-    pub starting_after: Option<String>,
-    pub previous_ending_before: Option<String>,
-    pub next_starting_after: Option<String>,
-    pub limit: usize,
-    pub order: Order,
-    pub previous_uri: Option<String>,
-    pub next_uri: Option<String>,
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -226,16 +417,20 @@ pub struct CurrencyPrice {
     pub currency: String,
 }
 
-#[derive(Deserialize)]
-struct CurrentTime {
-    iso: DateTime,
-}
-
 #[cfg(test)]
 mod test {
     use bigdecimal::FromPrimitive;
+    use futures::{StreamExt, TryStreamExt};
+    use http::StatusCode;
 
     use super::*;
+    use crate::error::ErrorCode;
+    use crate::transport::mock::MockTransport;
+
+    #[derive(Deserialize)]
+    struct CurrentTime {
+        iso: DateTime,
+    }
 
     #[test]
     fn test_currencies_deserialize() {
@@ -317,4 +512,201 @@ mod test {
             .unwrap();
         assert_eq!(1435082571, time.timestamp());
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_make_request_retries_server_errors() {
+        let transport = Arc::new(
+            MockTransport::default()
+                .respond(StatusCode::BAD_GATEWAY, "<html>502 Bad Gateway</html>")
+                .respond(
+                    StatusCode::OK,
+                    r#"{"data": {"iso": "2015-06-23T18:02:51Z", "epoch": 1435082571}}"#,
+                ),
+        );
+        let client = Public::with_transport("https://api.coinbase.com", transport.clone());
+
+        let time = client.current_time().await.unwrap();
+        assert_eq!(time.epoch, 1435082571);
+        assert_eq!(transport.uris().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_make_request_error_envelope() {
+        let transport = MockTransport::default().respond(
+            StatusCode::NOT_FOUND,
+            r#"{"errors": [{"id": "not_found", "message": "Not found"}]}"#,
+        );
+        let client = Public::with_transport("https://api.coinbase.com", transport);
+
+        let error = client.buy_price("BTC-XYZ").await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+        assert_eq!(error.code(), Some(&ErrorCode::NotFound));
+    }
+
+    #[tokio::test]
+    async fn test_get_stream_follows_next_uri() {
+        let transport = Arc::new(
+            MockTransport::default()
+                .respond(
+                    StatusCode::OK,
+                    r#"{
+    "pagination": {"limit": 1, "order": "asc", "next_starting_after": "AED", "next_uri": "/v2/currencies?limit=1&starting_after=AED"},
+    "data": [{"id": "AED", "name": "United Arab Emirates Dirham", "min_size": "0.01"}]
+}"#,
+                )
+                .respond(
+                    StatusCode::OK,
+                    r#"{
+    "pagination": {"limit": 1, "order": "asc", "next_uri": null},
+    "data": [{"id": "AFN", "name": "Afghan Afghani", "min_size": "0.01"}]
+}"#,
+                ),
+        );
+        let client = Public::with_transport("https://api.coinbase.com", transport.clone());
+
+        let pages: Vec<Page<Currency>> = client
+            .currencies(&ListOptions::new())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].next_cursor(), Some("AED"));
+        assert_eq!(pages[1].data[0].id, "AFN");
+        assert_eq!(pages[1].next_cursor(), None);
+        assert_eq!(
+            transport.uris(),
+            vec![
+                "https://api.coinbase.com/v2/currencies?limit=100",
+                "https://api.coinbase.com/v2/currencies?limit=1&starting_after=AED",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_stream_follows_previous_uri() {
+        let transport = Arc::new(
+            MockTransport::default()
+                .respond(
+                    StatusCode::OK,
+                    r#"{
+    "pagination": {"limit": 1, "order": "desc", "ending_before": "AFN", "previous_ending_before": "AED", "previous_uri": "/v2/currencies?limit=1&ending_before=AED", "next_uri": "/v2/currencies?limit=1&starting_after=AED"},
+    "data": [{"id": "AED", "name": "United Arab Emirates Dirham", "min_size": "0.01"}]
+}"#,
+                )
+                .respond(
+                    StatusCode::OK,
+                    r#"{
+    "pagination": {"limit": 1, "order": "desc", "ending_before": "AED", "previous_uri": null},
+    "data": [{"id": "ACC", "name": "Made-up Currency", "min_size": "0.01"}]
+}"#,
+                ),
+        );
+        let client = Public::with_transport("https://api.coinbase.com", transport.clone());
+        let options = ListOptions::new()
+            .limit(1)
+            .ending_before("AFN")
+            .direction(Direction::Backward);
+
+        let pages: Vec<Page<Currency>> = client.currencies(&options).try_collect().await.unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].previous_cursor(), Some("AED"));
+        assert_eq!(pages[1].data[0].id, "ACC");
+        assert_eq!(
+            transport.uris(),
+            vec![
+                "https://api.coinbase.com/v2/currencies?limit=1&ending_before=AFN",
+                "https://api.coinbase.com/v2/currencies?limit=1&ending_before=AED",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_stream_rejects_foreign_next_uri() {
+        let transport = MockTransport::default().respond(
+            StatusCode::OK,
+            r#"{
+    "pagination": {"limit": 1, "order": "asc", "next_uri": "https://evil.example.com/v2/currencies"},
+    "data": []
+}"#,
+        );
+        let client = Public::with_transport("https://api.coinbase.com/", transport);
+
+        let pages: Vec<Result<Page<Currency>>> =
+            client.currencies(&ListOptions::new()).collect().await;
+        assert_eq!(pages.len(), 2);
+        assert!(matches!(pages[1], Err(CBError::InvalidRequest(_))));
+    }
+
+    #[derive(Debug)]
+    struct Recorded {
+        endpoint: String,
+        status: Option<StatusCode>,
+        attempts: u32,
+        bytes: usize,
+        rate_limit: RateLimitStatus,
+    }
+
+    #[derive(Default)]
+    struct RecordingMetrics(std::sync::Mutex<Vec<Recorded>>);
+
+    impl Metrics for RecordingMetrics {
+        fn record(&self, metrics: &RequestMetrics<'_>) {
+            self.0.lock().unwrap().push(Recorded {
+                endpoint: metrics.endpoint.to_string(),
+                status: metrics.status,
+                attempts: metrics.attempts,
+                bytes: metrics.bytes,
+                rate_limit: metrics.rate_limit,
+            });
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_make_request_records_metrics() {
+        let body = r#"{"data": {"amount": "1010.25", "currency": "USD"}}"#;
+        let transport = MockTransport::default()
+            .respond(StatusCode::TOO_MANY_REQUESTS, "")
+            .respond_with_headers(
+                StatusCode::OK,
+                &[
+                    ("x-ratelimit-remaining", "9998"),
+                    ("x-ratelimit-reset", "60"),
+                ],
+                body,
+            );
+        let metrics = Arc::new(RecordingMetrics::default());
+        let client = Public::with_transport("https://api.coinbase.com", transport)
+            .with_metrics(metrics.clone());
+
+        client.buy_price("BTC-USD").await.unwrap();
+
+        let recorded = metrics.0.lock().unwrap();
+        assert_eq!(recorded.len(), 1);
+        let recorded = &recorded[0];
+        assert_eq!(recorded.endpoint, "/v2/prices/{pair}");
+        assert_eq!(recorded.status, Some(StatusCode::OK));
+        assert_eq!(recorded.attempts, 2);
+        assert_eq!(recorded.bytes, body.len());
+        assert_eq!(recorded.rate_limit.remaining, Some(9998));
+        assert_eq!(recorded.rate_limit.reset, Some(60));
+    }
+
+    #[tokio::test]
+    async fn test_build_failure_records_metrics() {
+        let metrics = Arc::new(RecordingMetrics::default());
+        let client = Public::with_transport("https://api.coinbase.com", MockTransport::default())
+            .with_metrics(metrics.clone());
+
+        let request = client
+            .request("/v2/time", "/v2/time")
+            .unwrap()
+            .header("X-Bad", "bad\nvalue");
+        assert!(client.make_empty_request(request).await.is_err());
+
+        let recorded = metrics.0.lock().unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].endpoint, "/v2/time");
+        assert_eq!(recorded[0].status, None);
+        assert_eq!(recorded[0].attempts, 1);
+    }
 }
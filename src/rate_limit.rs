@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Instant};
+
+///
+/// A request budget for a token bucket.
+///
+/// `requests` tokens are replenished evenly over `per`, and at most `burst` tokens can be banked
+/// while the client is idle.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limit {
+    pub requests: u32,
+    pub per: Duration,
+    pub burst: u32,
+}
+
+impl Limit {
+    ///
+    /// Coinbase allows 10,000 unauthenticated requests per hour per IP address.
+    ///
+    /// https://docs.cloud.coinbase.com/sign-in-with-coinbase/docs/rate-limiting
+    ///
+    pub const PUBLIC: Limit = Limit::new(10_000, Duration::from_secs(3600), 10);
+
+    ///
+    /// Coinbase allows 10,000 authenticated requests per hour per API key.
+    ///
+    /// https://docs.cloud.coinbase.com/sign-in-with-coinbase/docs/rate-limiting
+    ///
+    pub const PRIVATE: Limit = Limit::new(10_000, Duration::from_secs(3600), 10);
+
+    pub const fn new(requests: u32, per: Duration, burst: u32) -> Self {
+        Self {
+            requests,
+            per,
+            burst,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.per / self.requests.max(1)
+    }
+}
+
+///
+/// Async token bucket rate limiter with separate budgets for public and authenticated traffic.
+///
+/// Waiting tasks are served in the order they arrived, and waiting never blocks the runtime.
+/// Wrap it in an `Arc` to share one budget between several clients using the same API key.
+///
+#[derive(Debug)]
+pub struct RateLimiter {
+    public: Bucket,
+    private: Bucket,
+}
+
+impl RateLimiter {
+    pub fn new(public: Limit, private: Limit) -> Self {
+        Self {
+            public: Bucket::new(public),
+            private: Bucket::new(private),
+        }
+    }
+
+    /// Wait until the relevant budget allows another request to be sent.
+    pub async fn acquire(&self, authenticated: bool) {
+        if authenticated {
+            self.private.acquire().await
+        } else {
+            self.public.acquire().await
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(Limit::PUBLIC, Limit::PRIVATE)
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: Limit,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: Limit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(State {
+                tokens: f64::from(limit.burst.max(1)),
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    async fn acquire(&self) {
+        // tokio's Mutex is fair, so holding the lock while sleeping queues later callers behind
+        // this one in arrival order.
+        let mut state = self.state.lock().await;
+        self.refill(&mut state);
+
+        if state.tokens < 1.0 {
+            let wait = self.limit.interval().mul_f64(1.0 - state.tokens);
+            sleep(wait).await;
+            self.refill(&mut state);
+        }
+
+        state.tokens = (state.tokens - 1.0).max(0.0);
+    }
+
+    fn refill(&self, state: &mut State) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.updated);
+        let earned = elapsed.as_secs_f64() / self.limit.interval().as_secs_f64();
+        state.tokens = (state.tokens + earned).min(f64::from(self.limit.burst.max(1)));
+        state.updated = now;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_then_throttle() {
+        let limiter = RateLimiter::new(Limit::new(10, Duration::from_secs(10), 2), Limit::PRIVATE);
+
+        let start = Instant::now();
+        limiter.acquire(false).await;
+        limiter.acquire(false).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(0));
+
+        limiter.acquire(false).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_budgets_are_separate() {
+        let limiter = RateLimiter::new(
+            Limit::new(1, Duration::from_secs(60), 1),
+            Limit::new(1, Duration::from_secs(60), 1),
+        );
+
+        let start = Instant::now();
+        limiter.acquire(false).await;
+        limiter.acquire(true).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_waiters_are_served_in_order() {
+        let limiter = Arc::new(RateLimiter::new(
+            Limit::new(1, Duration::from_secs(1), 1),
+            Limit::PRIVATE,
+        ));
+        limiter.acquire(false).await;
+
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for i in 0..3 {
+            let limiter = limiter.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                limiter.acquire(false).await;
+                order.lock().unwrap().push(i);
+            }));
+            tokio::task::yield_now().await;
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+    }
+}
@@ -1,20 +1,34 @@
 use std::collections::HashMap;
 use std::result;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
 
 use hmac::{Hmac, Mac};
+use http::uri::InvalidUri;
 use http::{request, Method, Request, Uri, Version};
 use hyper::Body;
 use sha2::Sha256;
+use thiserror::Error;
 
-#[derive(Debug)]
-pub struct Error {}
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid uri {uri}: {source}")]
+    InvalidUri { uri: String, source: InvalidUri },
+    #[error("uri {0} points outside of the API root")]
+    ForeignUri(String),
+    #[error("system clock is before the unix epoch: {0}")]
+    Clock(#[from] SystemTimeError),
+    #[error(transparent)]
+    Http(#[from] http::Error),
+}
 
 pub type Result<T> = result::Result<T, Error>;
 
 type HmacSha256 = Hmac<Sha256>;
 
-const USER_AGENT: &str = concat!("coinbase-rs/", env!("CARGO_PKG_VERSION"));
+pub(crate) const USER_AGENT: &str = concat!("coinbase-rs/", env!("CARGO_PKG_VERSION"));
+
+/// The API version requested through the `CB-VERSION` header unless configured otherwise.
+pub const CB_VERSION: &str = "2021-01-01";
 
 #[derive(Clone, Debug, Default)]
 pub struct Parts {
@@ -31,11 +45,21 @@ pub struct Parts {
     pub headers: HashMap<String, String>,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct Builder {
     auth: Option<(String, String)>,
     parts: Parts,
     body: Vec<u8>,
+    retry_safe: bool,
+    cb_version: String,
+    user_agent: String,
+    endpoint: Option<&'static str>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder::new()
+    }
 }
 
 impl Builder {
@@ -44,11 +68,15 @@ impl Builder {
             auth: None,
             parts: Parts {
                 method: Method::GET,
-                uri: "/".parse().unwrap(),
+                uri: Uri::from_static("/"),
                 version: Version::default(),
                 headers: HashMap::new(),
             },
             body: Vec::new(),
+            retry_safe: false,
+            cb_version: CB_VERSION.to_string(),
+            user_agent: USER_AGENT.to_string(),
+            endpoint: None,
         }
     }
 
@@ -57,11 +85,15 @@ impl Builder {
             auth: Some((key.to_string(), secret.to_string())),
             parts: Parts {
                 method: Method::GET,
-                uri: "/".parse().unwrap(),
+                uri: Uri::from_static("/"),
                 version: Version::default(),
                 headers: HashMap::new(),
             },
             body: Vec::new(),
+            retry_safe: false,
+            cb_version: CB_VERSION.to_string(),
+            user_agent: USER_AGENT.to_string(),
+            endpoint: None,
         }
     }
 
@@ -89,37 +121,93 @@ impl Builder {
         _self
     }
 
-    pub fn body(self, body: &Vec<u8>) -> Builder {
+    pub fn body(self, body: &[u8]) -> Builder {
+        let mut _self = self;
+        _self.body = body.to_vec();
+        _self
+    }
+
+    ///
+    /// Marks a mutating request as safe to retry, because its body carries an `idem` token the
+    /// server deduplicates on.
+    ///
+    pub(crate) fn retry_safe(self) -> Builder {
         let mut _self = self;
-        _self.body = body.clone();
+        _self.retry_safe = true;
         _self
     }
 
-    pub fn build(self) -> Request<Body> {
+    ///
+    /// The one-time password sent as `CB-2FA-TOKEN`, for calls rejected with
+    /// `two_factor_required`. It isn't part of the signature, so the same request can be replayed
+    /// with it.
+    ///
+    pub fn two_factor_token(self, token: &str) -> Builder {
+        self.header("CB-2FA-TOKEN", token)
+    }
+
+    /// The API version date sent as `CB-VERSION`, e.g. `2021-01-01`.
+    pub fn cb_version(self, cb_version: &str) -> Builder {
+        let mut _self = self;
+        _self.cb_version = cb_version.to_string();
+        _self
+    }
+
+    pub fn user_agent(self, user_agent: &str) -> Builder {
+        let mut _self = self;
+        _self.user_agent = user_agent.to_string();
+        _self
+    }
+
+    ///
+    /// The endpoint template reported to metrics, e.g. `/v2/accounts/{account}`. Only needed
+    /// when the path contains ids, otherwise the path itself is used.
+    ///
+    pub fn endpoint(self, endpoint: &'static str) -> Builder {
+        let mut _self = self;
+        _self.endpoint = Some(endpoint);
+        _self
+    }
+
+    pub(crate) fn endpoint_template(&self) -> &str {
+        self.endpoint.unwrap_or_else(|| self.parts.uri.path())
+    }
+
+    pub(crate) fn parts(&self) -> &Parts {
+        &self.parts
+    }
+
+    pub(crate) fn is_retry_safe(&self) -> bool {
+        self.retry_safe
+    }
+
+    pub(crate) fn is_authenticated(&self) -> bool {
+        self.auth.is_some()
+    }
+
+    pub fn build(self) -> Result<Request<Body>> {
         let _self = if let Some((ref key, ref secret)) = self.auth {
-            let timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("leap-second")
-                .as_secs();
-
-            let sign = Self::sign(
-                secret,
-                timestamp,
-                &self.parts.method,
-                self.parts.uri.path_and_query().unwrap().as_str(),
-                &self.body,
-            );
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+            let path = match self.parts.uri.path_and_query() {
+                Some(path) => path.as_str(),
+                None => self.parts.uri.path(),
+            };
+            let sign = Self::sign(secret, timestamp, &self.parts.method, path, &self.body);
 
             self.clone()
-                .header("User-Agent", USER_AGENT)
                 .header("Content-Type", "Application/JSON")
-                .header("CB-VERSION", "2021-01-01")
                 .header("CB-ACCESS-KEY", key)
                 .header("CB-ACCESS-SIGN", &sign)
                 .header("CB-ACCESS-TIMESTAMP", &timestamp.to_string())
         } else {
             self
         };
+        let user_agent = _self.user_agent.clone();
+        let cb_version = _self.cb_version.clone();
+        let _self = _self
+            .header("User-Agent", &user_agent)
+            .header("CB-VERSION", &cb_version);
 
         let mut builder = request::Builder::new()
             .method(_self.parts.method)
@@ -127,15 +215,108 @@ impl Builder {
         for (key, value) in _self.parts.headers {
             builder = builder.header(&key, &value);
         }
-        builder.body(_self.body.into()).unwrap()
+        Ok(builder.body(_self.body.into())?)
     }
 
-    fn sign(secret: &str, timestamp: u64, method: &Method, path: &str, body: &Vec<u8>) -> String {
+    fn sign(secret: &str, timestamp: u64, method: &Method, path: &str, body: &[u8]) -> String {
         let mut mac: Hmac<Sha256> =
-            HmacSha256::new_varkey(&secret.as_bytes()).expect("Hmac::new(secret)");
+            HmacSha256::new_varkey(secret.as_bytes()).expect("Hmac::new(secret)");
         let input = timestamp.to_string() + method.as_str() + path;
         mac.input(input.as_bytes());
         mac.input(body);
         format!("{:x}", &mac.result().code())
     }
 }
+
+///
+/// Join a path, or a URI returned by the server, onto the API root.
+///
+/// A trailing slash on the root is ignored, and absolute URIs are only accepted when they point
+/// at the same API root, so signed requests never leave it.
+///
+pub(crate) fn join(root: &str, path: &str) -> Result<Uri> {
+    let root = root.trim_end_matches('/');
+    let uri = if path.starts_with("http://") || path.starts_with("https://") {
+        if !path.starts_with(root) || !matches!(path[root.len()..].chars().next(), Some('/') | None)
+        {
+            return Err(Error::ForeignUri(path.to_string()));
+        }
+        path.to_string()
+    } else if path.starts_with('/') {
+        format!("{}{}", root, path)
+    } else {
+        format!("{}/{}", root, path)
+    };
+
+    uri.parse()
+        .map_err(|source| Error::InvalidUri { uri, source })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_sends_version_and_user_agent() {
+        let request = Builder::default().build().unwrap();
+        assert_eq!(request.headers()["CB-VERSION"], CB_VERSION);
+        assert_eq!(request.headers()["User-Agent"], USER_AGENT);
+    }
+
+    #[test]
+    fn test_join() {
+        let root = "https://api.coinbase.com";
+        for base in &[root, "https://api.coinbase.com/"] {
+            assert_eq!(
+                join(base, "/v2/time").unwrap(),
+                "https://api.coinbase.com/v2/time"
+            );
+            assert_eq!(
+                join(base, "v2/time").unwrap(),
+                "https://api.coinbase.com/v2/time"
+            );
+        }
+        assert_eq!(
+            join(
+                root,
+                "https://api.coinbase.com/v2/accounts?starting_after=abc"
+            )
+            .unwrap(),
+            "https://api.coinbase.com/v2/accounts?starting_after=abc"
+        );
+    }
+
+    #[test]
+    fn test_join_rejects_bad_uris() {
+        let root = "https://api.coinbase.com";
+        assert!(matches!(
+            join(root, "https://evil.example.com/v2/accounts"),
+            Err(Error::ForeignUri(_))
+        ));
+        assert!(matches!(
+            join(root, "https://api.coinbase.com.evil.example.com/v2"),
+            Err(Error::ForeignUri(_))
+        ));
+        assert!(matches!(
+            join(root, "/v2/accounts?cursor=a b"),
+            Err(Error::InvalidUri { .. })
+        ));
+        assert!(matches!(
+            join("not a uri", "/v2/time"),
+            Err(Error::InvalidUri { .. })
+        ));
+    }
+
+    #[test]
+    fn test_build_without_path() {
+        let uri: Uri = "https://api.coinbase.com".parse().unwrap();
+        let request = Builder::new_with_auth("key", "secret").uri(uri).build();
+        assert!(request.is_ok());
+    }
+
+    #[test]
+    fn test_build_invalid_header() {
+        let request = Builder::new().header("bad header", "value").build();
+        assert!(matches!(request, Err(Error::Http(_))));
+    }
+}
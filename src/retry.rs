@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use http::{header, HeaderMap, Method, StatusCode};
+use rand::Rng;
+
+use crate::request;
+
+///
+/// Controls how transient failures (connection errors, 429 and 5xx responses) are retried.
+///
+/// By default only idempotent requests are retried, along with mutating requests that carry an
+/// `idem` token, such as sends, as the server will deduplicate those.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubled for every following attempt.
+    pub base_delay: Duration,
+
+    /// Upper bound for any single delay, including one requested through `Retry-After`.
+    pub max_delay: Duration,
+
+    /// Randomize each delay between half and all of its computed value.
+    pub jitter: bool,
+
+    /// Wait as long as the server asks for through the `Retry-After` header.
+    pub respect_retry_after: bool,
+
+    /// Also retry mutating requests that don't carry an `idem` token.
+    pub retry_non_idempotent: bool,
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn should_retry(&self, request: &request::Builder, attempt: u32) -> bool {
+        attempt < self.max_attempts
+            && (is_idempotent(&request.parts().method)
+                || request.is_retry_safe()
+                || self.retry_non_idempotent)
+    }
+
+    pub(crate) fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after.filter(|_| self.respect_retry_after) {
+            return retry_after.min(self.max_delay);
+        }
+
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        if self.jitter {
+            delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+        } else {
+            delay
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            respect_retry_after: true,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+        .to_std()
+        .ok()
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+#[cfg(test)]
+mod test {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            jitter: false,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_delay_backs_off_exponentially() {
+        let policy = policy();
+        assert_eq!(policy.delay(1, None), Duration::from_millis(500));
+        assert_eq!(policy.delay(2, None), Duration::from_millis(1000));
+        assert_eq!(policy.delay(3, None), Duration::from_millis(2000));
+        assert_eq!(policy.delay(100, None), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_delay_respects_retry_after() {
+        let policy = policy();
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(7))),
+            Duration::from_secs(7)
+        );
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(3600))),
+            Duration::from_secs(30)
+        );
+
+        let policy = RetryPolicy {
+            respect_retry_after: false,
+            ..policy
+        };
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(7))),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn test_jittered_delay_is_bounded() {
+        let policy = RetryPolicy::default();
+        for _ in 0..100 {
+            let delay = policy.delay(2, None);
+            assert!(delay >= Duration::from_millis(500));
+            assert!(delay <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = policy();
+        let get = request::Builder::new();
+        let post = request::Builder::new().method(Method::POST);
+        let idempotent_post = post.clone().retry_safe();
+
+        assert!(policy.should_retry(&get, 1));
+        assert!(policy.should_retry(&get, 2));
+        assert!(!policy.should_retry(&get, 3));
+        assert!(!policy.should_retry(&post, 1));
+        assert!(policy.should_retry(&idempotent_post, 1));
+        assert!(!RetryPolicy::none().should_retry(&get, 1));
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::OK));
+    }
+
+    #[test]
+    fn test_retry_after_parse() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(header::RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+}
@@ -0,0 +1,164 @@
+//!
+//! Optional `tracing` instrumentation, enabled through the `tracing` feature. Every function is
+//! a no-op without it.
+//!
+//! Credentials are never recorded: the signing headers, bearer tokens and 2FA tokens are
+//! redacted, and response bodies are only logged at trace level, truncated.
+//!
+
+use std::future::Future;
+use std::time::Duration;
+
+use http::{Request, StatusCode};
+use hyper::Body;
+
+use crate::request;
+
+#[cfg(feature = "tracing")]
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cb-access-key",
+    "cb-access-sign",
+    "cb-2fa-token",
+];
+
+#[cfg(feature = "tracing")]
+const MAX_BODY_LOG: usize = 1024;
+
+/// Run `future` in a span describing one API call, across all of its attempts.
+#[cfg(feature = "tracing")]
+pub(crate) fn request<F: Future>(
+    request: &request::Builder,
+    future: F,
+) -> impl Future<Output = F::Output> {
+    use tracing::Instrument;
+
+    let parts = request.parts();
+    let span = tracing::info_span!(
+        "coinbase.request",
+        method = %parts.method,
+        path = parts.uri.path(),
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+        retries = tracing::field::Empty,
+    );
+    future.instrument(span)
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn request<F: Future>(_request: &request::Builder, future: F) -> F {
+    future
+}
+
+/// Run `future` in a span for one page of a paginated stream, counting from 1.
+#[cfg(feature = "tracing")]
+pub(crate) fn page<F: Future>(page: usize, future: F) -> impl Future<Output = F::Output> {
+    use tracing::Instrument;
+
+    future.instrument(tracing::info_span!("coinbase.page", page))
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn page<F: Future>(_page: usize, future: F) -> F {
+    future
+}
+
+pub(crate) fn attempt(request: &Request<Body>, attempt: u32) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        attempt,
+        uri = %request.uri(),
+        headers = ?redacted_headers(request.headers()),
+        "sending request"
+    );
+    #[cfg(not(feature = "tracing"))]
+    let _ = (request, attempt);
+}
+
+pub(crate) fn retry(attempt: u32, delay: Duration, status: Option<StatusCode>) {
+    #[cfg(feature = "tracing")]
+    {
+        tracing::Span::current().record("retries", attempt);
+        tracing::warn!(
+            attempt,
+            delay_ms = delay.as_millis() as u64,
+            status = status.map(|status| status.as_u16()),
+            "retrying request"
+        );
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = (attempt, delay, status);
+}
+
+pub(crate) fn response(status: StatusCode, latency: Duration, body: &[u8]) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        span.record("status", status.as_u16());
+        span.record("latency_ms", latency.as_millis() as u64);
+        tracing::trace!(body = %truncate(body), "response body");
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = (status, latency, body);
+}
+
+#[cfg(feature = "tracing")]
+fn redacted_headers(headers: &http::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+                "<redacted>".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+#[cfg(feature = "tracing")]
+fn truncate(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    match body.char_indices().nth(MAX_BODY_LOG) {
+        Some((end, _)) => format!("{}... ({} bytes)", &body[..end], body.len()),
+        None => body.into_owned(),
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_redacted_headers() {
+        let request = request::Builder::new_with_auth("my-key", "my-secret")
+            .header("Authorization", "Bearer my-token")
+            .header("CB-2FA-TOKEN", "123456")
+            .build()
+            .unwrap();
+
+        let headers = redacted_headers(request.headers());
+        let rendered = format!("{:?}", headers);
+        assert!(!rendered.contains("my-key"));
+        assert!(!rendered.contains("my-token"));
+        assert!(!rendered.contains("123456"));
+        assert!(headers
+            .iter()
+            .any(|(name, value)| name == "cb-access-sign" && value == "<redacted>"));
+        assert!(headers
+            .iter()
+            .any(|(name, value)| name == "cb-version" && value == request::CB_VERSION));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate(b"short"), "short");
+
+        let long = "x".repeat(MAX_BODY_LOG + 10);
+        let truncated = truncate(long.as_bytes());
+        assert!(truncated.starts_with(&"x".repeat(MAX_BODY_LOG)));
+        assert!(truncated.ends_with(&format!("... ({} bytes)", MAX_BODY_LOG + 10)));
+    }
+}
@@ -0,0 +1,294 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use http::{Request, Response};
+use hyper::client::connect::Connect;
+use hyper::{client::HttpConnector, Body, Client};
+
+use crate::proxy::{Proxy, ProxyConnector};
+#[cfg(feature = "rustls-native-roots")]
+use crate::CBError;
+use crate::Result;
+
+///
+/// Sends fully built, signed requests over the wire.
+///
+/// `Public` and `Private` go through this trait for every call, which makes it possible to reuse
+/// an already configured HTTP stack, or to serve canned responses in tests.
+///
+pub trait Transport: Send + Sync {
+    fn send(&self, request: Request<Body>) -> BoxFuture<'_, Result<Response<Body>>>;
+}
+
+impl<T> Transport for Arc<T>
+where
+    T: Transport + ?Sized,
+{
+    fn send(&self, request: Request<Body>) -> BoxFuture<'_, Result<Response<Body>>> {
+        (**self).send(request)
+    }
+}
+
+///
+/// The default transport, a hyper `Client`.
+///
+#[derive(Clone, Debug)]
+pub struct HyperTransport<C = HttpsConnector<ProxyConnector>> {
+    client: Client<C>,
+}
+
+impl HyperTransport {
+    ///
+    /// # Panics
+    ///
+    /// With the `rustls-native-roots` feature, if the platform's root certificates can't be
+    /// loaded. `ClientBuilder` reports that as a `CBError::Config` instead.
+    ///
+    pub fn new() -> Self {
+        Self::with_options(&Options::default()).expect("could not set up TLS")
+    }
+
+    pub(crate) fn with_options(options: &Options) -> Result<Self> {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(options.connect_timeout);
+
+        let client = Client::builder()
+            .pool_idle_timeout(options.pool_idle_timeout)
+            .pool_max_idle_per_host(options.pool_max_idle_per_host)
+            .build::<_, Body>(https_connector(ProxyConnector::new(
+                http,
+                options.proxy.clone(),
+            ))?);
+
+        Ok(Self { client })
+    }
+}
+
+///
+/// The TLS connector selected through cargo features, rustls takes precedence over native-tls
+/// when both are enabled.
+///
+#[cfg(any(feature = "rustls-webpki-roots", feature = "rustls-native-roots"))]
+pub type HttpsConnector<H> = hyper_rustls::HttpsConnector<H>;
+
+///
+/// The TLS connector selected through cargo features, rustls takes precedence over native-tls
+/// when both are enabled.
+///
+#[cfg(all(
+    feature = "native-tls",
+    not(any(feature = "rustls-webpki-roots", feature = "rustls-native-roots"))
+))]
+pub type HttpsConnector<H> = hyper_tls::HttpsConnector<H>;
+
+#[cfg(feature = "rustls-native-roots")]
+fn https_connector(connector: ProxyConnector) -> Result<HttpsConnector<ProxyConnector>> {
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(native_roots(rustls_native_certs::load_native_certs())?)
+        .with_no_client_auth();
+    Ok(hyper_rustls::HttpsConnectorBuilder::new()
+        .with_tls_config(config)
+        .https_or_http()
+        .enable_http1()
+        .wrap_connector(connector))
+}
+
+// The platform's root certificates, failing rather than trusting nothing when there are none,
+// e.g. in a distroless image without a CA bundle.
+#[cfg(feature = "rustls-native-roots")]
+fn native_roots(
+    certs: std::io::Result<Vec<rustls_native_certs::Certificate>>,
+) -> Result<rustls::RootCertStore> {
+    let certs = certs.map_err(|e| {
+        CBError::Config(format!(
+            "could not load the platform's root certificates: {}",
+            e
+        ))
+    })?;
+    let mut roots = rustls::RootCertStore::empty();
+    let certs: Vec<_> = certs.into_iter().map(|cert| cert.0).collect();
+    roots.add_parsable_certificates(&certs);
+    if roots.is_empty() {
+        return Err(CBError::Config(
+            "no usable root certificates found on this platform".to_string(),
+        ));
+    }
+    Ok(roots)
+}
+
+#[cfg(all(feature = "rustls-webpki-roots", not(feature = "rustls-native-roots")))]
+fn https_connector(connector: ProxyConnector) -> Result<HttpsConnector<ProxyConnector>> {
+    Ok(hyper_rustls::HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .wrap_connector(connector))
+}
+
+#[cfg(all(
+    feature = "native-tls",
+    not(any(feature = "rustls-webpki-roots", feature = "rustls-native-roots"))
+))]
+fn https_connector(connector: ProxyConnector) -> Result<HttpsConnector<ProxyConnector>> {
+    Ok(hyper_tls::HttpsConnector::new_with_connector(connector))
+}
+
+// Stand-ins without any TLS feature, so the `compile_error!` in lib.rs is the only error reported.
+#[cfg(not(any(
+    feature = "native-tls",
+    feature = "rustls-webpki-roots",
+    feature = "rustls-native-roots"
+)))]
+pub type HttpsConnector<H> = H;
+
+#[cfg(not(any(
+    feature = "native-tls",
+    feature = "rustls-webpki-roots",
+    feature = "rustls-native-roots"
+)))]
+fn https_connector(connector: ProxyConnector) -> Result<HttpsConnector<ProxyConnector>> {
+    Ok(connector)
+}
+
+/// Connection settings for the default transport.
+#[derive(Clone, Debug)]
+pub(crate) struct Options {
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) pool_idle_timeout: Option<Duration>,
+    pub(crate) pool_max_idle_per_host: usize,
+    pub(crate) proxy: Option<Proxy>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            connect_timeout: None,
+            pool_idle_timeout: Some(Duration::from_secs(30)),
+            pool_max_idle_per_host: usize::MAX,
+            proxy: None,
+        }
+    }
+}
+
+impl Default for HyperTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> From<Client<C>> for HyperTransport<C> {
+    fn from(client: Client<C>) -> Self {
+        Self { client }
+    }
+}
+
+impl<C> Transport for HyperTransport<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    fn send(&self, request: Request<Body>) -> BoxFuture<'_, Result<Response<Body>>> {
+        let response = self.client.request(request);
+        Box::pin(async move { Ok(response.await?) })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    use http::StatusCode;
+
+    use super::*;
+
+    type CannedResponse = (StatusCode, Vec<(&'static str, &'static str)>, String);
+
+    ///
+    /// Serves canned responses in order, and records every request it was sent.
+    ///
+    #[derive(Default)]
+    pub(crate) struct MockTransport {
+        responses: Mutex<VecDeque<CannedResponse>>,
+        pub(crate) requests: Mutex<Vec<Request<Vec<u8>>>>,
+    }
+
+    impl MockTransport {
+        pub(crate) fn respond(self, status: StatusCode, body: &str) -> Self {
+            self.respond_with_headers(status, &[], body)
+        }
+
+        pub(crate) fn respond_with_headers(
+            self,
+            status: StatusCode,
+            headers: &[(&'static str, &'static str)],
+            body: &str,
+        ) -> Self {
+            self.responses
+                .lock()
+                .unwrap()
+                .push_back((status, headers.to_vec(), body.to_string()));
+            self
+        }
+
+        pub(crate) fn uris(&self) -> Vec<String> {
+            self.requests
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|request| request.uri().to_string())
+                .collect()
+        }
+    }
+
+    impl Transport for MockTransport {
+        fn send(&self, request: Request<Body>) -> BoxFuture<'_, Result<Response<Body>>> {
+            Box::pin(async move {
+                let (parts, body) = request.into_parts();
+                let body = hyper::body::to_bytes(body).await?.to_vec();
+                self.requests
+                    .lock()
+                    .unwrap()
+                    .push(Request::from_parts(parts, body));
+
+                let (status, headers, body) = self
+                    .responses
+                    .lock()
+                    .unwrap()
+                    .pop_front()
+                    .expect("no canned response left");
+                let mut response = Response::builder().status(status);
+                for (name, value) in headers {
+                    response = response.header(name, value);
+                }
+                Ok(response.body(Body::from(body)).unwrap())
+            })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rustls-native-roots"))]
+mod test {
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn test_native_roots_errors() {
+        let unreadable = native_roots(Err(io::Error::new(io::ErrorKind::NotFound, "no CA bundle")));
+        assert!(matches!(unreadable, Err(CBError::Config(ref e)) if e.contains("no CA bundle")));
+
+        assert!(matches!(
+            native_roots(Ok(Vec::new())),
+            Err(CBError::Config(_))
+        ));
+        assert!(matches!(
+            native_roots(Ok(vec![rustls_native_certs::Certificate(
+                b"not DER".to_vec()
+            )])),
+            Err(CBError::Config(_))
+        ));
+    }
+}